@@ -0,0 +1,115 @@
+//! Compare the path compression strategies of `DisjointSets`.
+//!
+//! Run with `cargo bench --bench compression`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use union_find::compression::{
+    Compression, FullCompression, NoCompression, PathHalving, PathSplitting,
+};
+use union_find::disjoint_sets::{DisjointSets, DisjointSetsParts};
+use union_find::link::BySize;
+use union_find::union_find::UnionFind;
+
+#[path = "../src/test_rng.rs"]
+mod test_rng;
+
+use test_rng::TestRng;
+
+const N: u32 = 200_000;
+const RUNS: u32 = 5;
+
+/// A fixed pseudo-random sequence of item pairs.
+fn pairs(len: u32) -> Vec<(u32, u32)> {
+    let mut rng = TestRng::new();
+    (0..len)
+        .map(|_| (rng.below(N as u64) as u32, rng.below(N as u64) as u32))
+        .collect()
+}
+
+/// Run `f` on a fresh input several times and return the fastest run.
+fn time<I>(mut setup: impl FnMut() -> I, mut f: impl FnMut(I)) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let input = setup();
+            let start = Instant::now();
+            f(input);
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+/// Random unions followed by random `same_set` queries.
+fn random<C: Compression>(edges: &[(u32, u32)], queries: &[(u32, u32)]) -> Duration {
+    time(
+        || {
+            let mut sets: DisjointSets<u32, BySize, C> = DisjointSets::with_link_policy(BySize);
+            for i in 0..N {
+                sets.make_set(i).unwrap();
+            }
+            sets
+        },
+        |mut sets| {
+            for (x, y) in edges {
+                sets.union(x, y).unwrap();
+            }
+            for (x, y) in queries {
+                black_box(sets.same_set(x, y).unwrap());
+            }
+        },
+    )
+}
+
+/// Finds from every item of a single long chain, as can result from
+/// deserializing an unbalanced tree.
+fn chain<C: Compression>() -> Duration {
+    time(
+        || {
+            let parts = DisjointSetsParts {
+                items: (0..N).collect(),
+                parents: (1..N as usize).chain([N as usize - 1]).collect(),
+            };
+            let sets: DisjointSets<u32, BySize, C> =
+                DisjointSets::from_parts_with_link_policy(parts, BySize).unwrap();
+            sets
+        },
+        |mut sets| {
+            for i in (0..N).step_by(N as usize / 100) {
+                black_box(sets.find(&i).unwrap());
+            }
+        },
+    )
+}
+
+fn report(name: &str, random: Duration, chain: Duration) {
+    println!("{:<16} {:>12.2?} {:>12.2?}", name, random, chain);
+}
+
+fn main() {
+    let edges = pairs(N);
+    let queries = pairs(2 * N);
+
+    println!("{:<16} {:>12} {:>12}", "strategy", "random", "chain");
+    report(
+        "full",
+        random::<FullCompression>(&edges, &queries),
+        chain::<FullCompression>(),
+    );
+    report(
+        "halving",
+        random::<PathHalving>(&edges, &queries),
+        chain::<PathHalving>(),
+    );
+    report(
+        "splitting",
+        random::<PathSplitting>(&edges, &queries),
+        chain::<PathSplitting>(),
+    );
+    report(
+        "none",
+        random::<NoCompression>(&edges, &queries),
+        chain::<NoCompression>(),
+    );
+}
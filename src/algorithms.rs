@@ -0,0 +1,5 @@
+//! Algorithms built on the union-find data structures of this crate.
+
+pub mod dendrogram;
+pub mod kruskal;
+pub mod offline_connectivity;
@@ -0,0 +1,282 @@
+//! Single-linkage hierarchical clustering.
+
+use std::hash::Hash;
+
+use crate::algorithms::kruskal::Kruskal;
+use crate::disjoint_sets::DisjointSets;
+use crate::item_table::ItemTable;
+use crate::union_find::{Arg, Error, Result, UnionFind};
+
+/// A merge of two clusters into a new one.
+///
+/// Clusters are numbered like in SciPy: the items are the clusters
+/// `0..num_items` in insertion order, and the `i`-th merge creates the
+/// cluster `num_items + i`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Merge {
+    pub left: usize,
+    pub right: usize,
+    pub distance: f64,
+    /// The number of items in the new cluster.
+    pub size: usize,
+}
+
+/// The hierarchy of merges performed by a sequence of unions.
+///
+/// Items must be added before the first union, since merges are numbered
+/// after the items. For the linkage matrix to be valid in SciPy, unions must
+/// also be performed by non-decreasing distance, which
+/// [`Dendrogram::single_linkage`] takes care of.
+#[derive(Clone, Debug)]
+pub struct Dendrogram<T> {
+    items: ItemTable<T>,
+    sets: DisjointSets<usize>,
+    /// The cluster of every representative in `sets`.
+    clusters: Vec<usize>,
+    merges: Vec<Merge>,
+    /// The items passed to the union that created each merge.
+    links: Vec<(usize, usize)>,
+}
+
+impl<T> Default for Dendrogram<T>
+where
+    T: Eq + Hash + Clone,
+{
+    fn default() -> Self {
+        Dendrogram::new()
+    }
+}
+
+impl<T> Dendrogram<T>
+where
+    T: Eq + Hash + Clone,
+{
+    pub fn new() -> Self {
+        Dendrogram {
+            items: ItemTable::new(),
+            sets: DisjointSets::new(),
+            clusters: Vec::new(),
+            merges: Vec::new(),
+            links: Vec::new(),
+        }
+    }
+
+    /// Cluster `items` by single linkage, given the distances between pairs of
+    /// items as `edges`. Pairs that are not listed are never merged directly.
+    pub fn single_linkage(
+        items: impl IntoIterator<Item = T>,
+        edges: impl IntoIterator<Item = (T, T, f64)>,
+    ) -> Self {
+        let edges: Vec<(T, T, f64)> = edges.into_iter().collect();
+        let mut dendrogram = Dendrogram::new();
+        for item in items
+            .into_iter()
+            .chain(edges.iter().flat_map(|(x, y, _)| [x.clone(), y.clone()]))
+        {
+            // Skip duplicates.
+            let _ = dendrogram.make_set(item);
+        }
+
+        let forest = Kruskal::new().run([], edges);
+        for (x, y, distance) in forest.edges {
+            dendrogram
+                .union(&x, &y, distance)
+                .expect("every endpoint is in the dendrogram");
+        }
+        dendrogram
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.items.contains(item)
+    }
+
+    /// Get the items in insertion order, which is their cluster number.
+    pub fn items(&self) -> &[T] {
+        self.items.items()
+    }
+
+    pub fn num_items(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn num_clusters(&self) -> usize {
+        self.sets.num_sets()
+    }
+
+    /// Get the merges in the order they were performed.
+    pub fn merges(&self) -> &[Merge] {
+        &self.merges
+    }
+
+    /// Add `item` as a singleton cluster. If `item` already exists, an error
+    /// is returned. If a merge was already recorded, [`Error::ItemRejected`]
+    /// is returned, since the number of `item` would be taken by the merge.
+    pub fn make_set(&mut self, item: T) -> Result<()> {
+        if self.contains(&item) {
+            return Err(Error::item_exists());
+        }
+        if !self.merges.is_empty() {
+            return Err(Error::item_rejected());
+        }
+
+        let index = self.items.insert(item)?;
+        self.sets.make_set(index)?;
+        self.clusters.push(index);
+        Ok(())
+    }
+
+    /// Merge the clusters containing `x` and `y` at `distance`. Returns
+    /// whether a merge was recorded, which is not the case if they are
+    /// already in the same cluster. If `x` or `y` do not exist, an error is
+    /// returned.
+    pub fn union(&mut self, x: &T, y: &T, distance: f64) -> Result<bool> {
+        let x = self.items.index_of(x, Arg::X)?;
+        let y = self.items.index_of(y, Arg::Y)?;
+        let x_repr = *self.sets.find(&x)?;
+        let y_repr = *self.sets.find(&y)?;
+        if x_repr == y_repr {
+            return Ok(false);
+        }
+
+        let (left, right) = (self.clusters[x_repr], self.clusters[y_repr]);
+        self.sets.union(&x_repr, &y_repr)?;
+        let repr = *self.sets.find(&x_repr)?;
+        self.clusters[repr] = self.items.len() + self.merges.len();
+        self.merges.push(Merge {
+            left: left.min(right),
+            right: left.max(right),
+            distance,
+            size: self.sets.set_size(&repr)?,
+        });
+        self.links.push((x, y));
+        Ok(true)
+    }
+
+    /// Get the merges as a SciPy linkage matrix: one row of
+    /// `[left, right, distance, size]` per merge.
+    pub fn linkage_matrix(&self) -> Vec<[f64; 4]> {
+        self.merges
+            .iter()
+            .map(|merge| {
+                [
+                    merge.left as f64,
+                    merge.right as f64,
+                    merge.distance,
+                    merge.size as f64,
+                ]
+            })
+            .collect()
+    }
+
+    /// Get the flat clusters formed by the merges at a distance of at most
+    /// `threshold`.
+    pub fn cut_at_distance(&self, threshold: f64) -> DisjointSets<T> {
+        self.flatten(
+            self.merges
+                .iter()
+                .zip(&self.links)
+                .filter(|(merge, _)| merge.distance <= threshold)
+                .map(|(_, link)| link),
+        )
+    }
+
+    /// Get the flat clusters formed by the first merges, stopping once there
+    /// are at most `num_clusters` clusters. There are more clusters if the
+    /// merges never connect all items.
+    pub fn cut_into(&self, num_clusters: usize) -> DisjointSets<T> {
+        let num_merges = self.items.len().saturating_sub(num_clusters);
+        self.flatten(self.links.iter().take(num_merges))
+    }
+
+    fn flatten<'a>(&self, links: impl Iterator<Item = &'a (usize, usize)>) -> DisjointSets<T> {
+        let mut sets: DisjointSets<T> = self.items.items().iter().cloned().collect();
+        for &(x, y) in links {
+            sets.union(&self.items[x], &self.items[y])
+                .expect("every item is in the flat clusters");
+        }
+        sets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dendrogram() {
+        let mut dendrogram = Dendrogram::new();
+        for item in ["a", "b", "c", "d", "e"] {
+            dendrogram.make_set(item).unwrap();
+        }
+        assert!(matches!(
+            dendrogram.make_set("a"),
+            Err(Error::ItemExists { .. })
+        ));
+        assert!(matches!(
+            dendrogram.union(&"a", &"z", 1.0),
+            Err(Error::ItemNotFound { arg: Arg::Y, .. })
+        ));
+
+        assert!(!dendrogram.union(&"a", &"a", 0.5).unwrap());
+        assert!(dendrogram.union(&"d", &"c", 1.0).unwrap());
+        assert!(matches!(
+            dendrogram.make_set("f"),
+            Err(Error::ItemRejected { .. })
+        ));
+        assert!(!dendrogram.contains(&"f"));
+        assert!(dendrogram.union(&"a", &"b", 2.0).unwrap());
+        assert!(!dendrogram.union(&"c", &"d", 2.5).unwrap());
+        assert!(dendrogram.union(&"b", &"c", 3.0).unwrap());
+        assert_eq!(dendrogram.num_clusters(), 2);
+        assert_eq!(
+            dendrogram.linkage_matrix(),
+            vec![
+                [2.0, 3.0, 1.0, 2.0],
+                [0.0, 1.0, 2.0, 2.0],
+                [5.0, 6.0, 3.0, 4.0]
+            ]
+        );
+
+        let mut clusters = dendrogram.cut_at_distance(2.0);
+        assert_eq!(clusters.num_sets(), 3);
+        assert!(clusters.same_set(&"a", &"b").unwrap());
+        assert!(!clusters.same_set(&"a", &"c").unwrap());
+
+        let mut clusters = dendrogram.cut_into(2);
+        assert_eq!(clusters.num_sets(), 2);
+        assert!(clusters.same_set(&"a", &"d").unwrap());
+        assert!(!clusters.same_set(&"a", &"e").unwrap());
+
+        assert_eq!(dendrogram.cut_into(1).num_sets(), 2);
+        assert_eq!(dendrogram.cut_into(5).num_sets(), 5);
+        assert_eq!(dendrogram.cut_at_distance(0.5).num_sets(), 5);
+    }
+
+    #[test]
+    fn test_single_linkage() {
+        let edges = [
+            (0, 1, 0.5),
+            (1, 2, 4.0),
+            (0, 2, 1.5),
+            (3, 4, 0.25),
+            (2, 3, 2.0),
+        ];
+        let dendrogram = Dendrogram::single_linkage([0, 1, 2, 3, 4, 5], edges);
+        assert_eq!(dendrogram.items(), &[0, 1, 2, 3, 4, 5]);
+        assert_eq!(
+            dendrogram.linkage_matrix(),
+            vec![
+                [3.0, 4.0, 0.25, 2.0],
+                [0.0, 1.0, 0.5, 2.0],
+                [2.0, 7.0, 1.5, 3.0],
+                [6.0, 8.0, 2.0, 5.0]
+            ]
+        );
+        assert_eq!(dendrogram.num_clusters(), 2);
+
+        let mut clusters = dendrogram.cut_into(3);
+        assert!(clusters.same_set(&0, &2).unwrap());
+        assert!(clusters.same_set(&3, &4).unwrap());
+        assert!(!clusters.same_set(&2, &3).unwrap());
+    }
+}
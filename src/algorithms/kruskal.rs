@@ -0,0 +1,204 @@
+//! Minimum spanning forests with Kruskal's algorithm.
+
+use std::cmp::Ordering;
+use std::hash::Hash;
+use std::ops::Add;
+
+use crate::dense_disjoint_sets::DenseDisjointSets;
+use crate::disjoint_sets::DisjointSets;
+use crate::union_find::{Result, UnionFind};
+
+/// A minimum spanning forest, as computed by [`Kruskal`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpanningForest<T, W> {
+    /// The edges of the forest, by increasing weight.
+    pub edges: Vec<(T, T, W)>,
+    /// The sum of the weights of `edges`.
+    pub total_weight: W,
+    /// The number of trees in the forest, counting isolated items.
+    pub num_components: usize,
+}
+
+/// Kruskal's algorithm: edges are visited by increasing weight, and every edge
+/// that joins two different components is added to the forest.
+///
+/// Ties are broken by the order of the edges in the input. Weights only need
+/// to be `PartialOrd`, but comparing two weights must not fail, so `NaN`
+/// weights cause a panic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Kruskal {
+    min_components: usize,
+}
+
+impl Default for Kruskal {
+    fn default() -> Self {
+        Kruskal::new()
+    }
+}
+
+impl Kruskal {
+    /// Create a `Kruskal` that computes the full minimum spanning forest.
+    pub fn new() -> Self {
+        Kruskal { min_components: 1 }
+    }
+
+    /// Stop adding edges once the forest has at most `k` components, which
+    /// gives a single-linkage clustering into `k` clusters.
+    pub fn stop_at(mut self, k: usize) -> Self {
+        self.min_components = k;
+        self
+    }
+
+    /// Compute the minimum spanning forest of the graph with the vertices
+    /// `items` and the weighted `edges`. Endpoints of edges are vertices too,
+    /// so `items` only needs to list isolated vertices.
+    pub fn run<T, W>(
+        &self,
+        items: impl IntoIterator<Item = T>,
+        edges: impl IntoIterator<Item = (T, T, W)>,
+    ) -> SpanningForest<T, W>
+    where
+        T: Eq + Hash + Clone,
+        W: PartialOrd + Add<Output = W> + Default + Clone,
+    {
+        let edges: Vec<(T, T, W)> = edges.into_iter().collect();
+        let mut sets: DisjointSets<T> = items.into_iter().collect();
+        for (x, y, _) in &edges {
+            sets.find_or_insert(x.clone());
+            sets.find_or_insert(y.clone());
+        }
+
+        let num_sets = sets.num_sets();
+        self.run_with(&mut sets, num_sets, edges)
+            .expect("every endpoint is in the disjoint sets")
+    }
+
+    /// Compute the minimum spanning forest of the graph with the vertices
+    /// `0..num_items` and the weighted `edges`. If an endpoint is out of
+    /// range, an error is returned.
+    pub fn run_dense<W>(
+        &self,
+        num_items: usize,
+        edges: impl IntoIterator<Item = (u32, u32, W)>,
+    ) -> Result<SpanningForest<u32, W>>
+    where
+        W: PartialOrd + Add<Output = W> + Default + Clone,
+    {
+        let mut sets = DenseDisjointSets::with_capacity(num_items);
+        sets.grow(num_items);
+        self.run_with(&mut sets, num_items, edges.into_iter().collect())
+    }
+
+    fn run_with<T, W>(
+        &self,
+        sets: &mut impl UnionFind<T>,
+        mut num_components: usize,
+        mut edges: Vec<(T, T, W)>,
+    ) -> Result<SpanningForest<T, W>>
+    where
+        W: PartialOrd + Add<Output = W> + Default + Clone,
+    {
+        edges.sort_by(|(_, _, v), (_, _, w)| compare_weights(v, w));
+
+        let mut forest = Vec::new();
+        let mut total_weight = W::default();
+        for (x, y, weight) in edges {
+            if num_components <= self.min_components {
+                break;
+            }
+            if sets.same_set(&x, &y)? {
+                continue;
+            }
+
+            sets.union(&x, &y)?;
+            num_components -= 1;
+            total_weight = total_weight + weight.clone();
+            forest.push((x, y, weight));
+        }
+
+        Ok(SpanningForest {
+            edges: forest,
+            total_weight,
+            num_components,
+        })
+    }
+}
+
+/// Compute the minimum spanning forest of the graph with the weighted `edges`.
+/// See [`Kruskal`] for isolated vertices and stopping early.
+pub fn kruskal<T, W>(edges: impl IntoIterator<Item = (T, T, W)>) -> SpanningForest<T, W>
+where
+    T: Eq + Hash + Clone,
+    W: PartialOrd + Add<Output = W> + Default + Clone,
+{
+    Kruskal::new().run([], edges)
+}
+
+fn compare_weights<W: PartialOrd>(v: &W, w: &W) -> Ordering {
+    v.partial_cmp(w).expect("edge weights must be comparable")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::union_find::{Arg, Error};
+
+    #[test]
+    fn test_kruskal() {
+        let edges = [
+            ("a", "b", 4),
+            ("a", "c", 1),
+            ("b", "c", 2),
+            ("b", "d", 5),
+            ("c", "d", 8),
+            ("d", "e", 3),
+            ("x", "y", 7),
+        ];
+        let forest = kruskal(edges);
+        assert_eq!(
+            forest.edges,
+            vec![
+                ("a", "c", 1),
+                ("b", "c", 2),
+                ("d", "e", 3),
+                ("b", "d", 5),
+                ("x", "y", 7)
+            ]
+        );
+        assert_eq!(forest.total_weight, 18);
+        assert_eq!(forest.num_components, 2);
+
+        let forest = Kruskal::new().run(["z"], edges);
+        assert_eq!(forest.total_weight, 18);
+        assert_eq!(forest.num_components, 3);
+
+        let forest = Kruskal::new().stop_at(5).run(["z"], edges);
+        assert_eq!(
+            forest.edges,
+            vec![("a", "c", 1), ("b", "c", 2), ("d", "e", 3)]
+        );
+        assert_eq!(forest.total_weight, 6);
+        assert_eq!(forest.num_components, 5);
+
+        let forest = Kruskal::new().stop_at(4).run(["z"], edges);
+        assert_eq!(forest.total_weight, 11);
+        assert_eq!(forest.num_components, 4);
+    }
+
+    #[test]
+    fn test_kruskal_dense() {
+        let edges = [(0, 1, 0.5), (1, 2, 0.25), (0, 2, 0.125), (3, 4, 1.0)];
+        let forest = Kruskal::new().run_dense(6, edges).unwrap();
+        assert_eq!(forest.edges, vec![(0, 2, 0.125), (1, 2, 0.25), (3, 4, 1.0)]);
+        assert_eq!(forest.total_weight, 1.375);
+        assert_eq!(forest.num_components, 3);
+
+        let forest = Kruskal::new().stop_at(0).run_dense(6, edges).unwrap();
+        assert_eq!(forest.num_components, 3);
+
+        assert!(matches!(
+            Kruskal::new().run_dense(3, [(0, 1, 1), (1, 3, 2)]),
+            Err(Error::ItemNotFound { arg: Arg::Y, .. })
+        ));
+    }
+}
@@ -0,0 +1,229 @@
+//! Connectivity queries on a graph whose edges are added and removed, when
+//! all operations are known in advance.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::rollback_disjoint_sets::RollbackDisjointSets;
+use crate::union_find::UnionFind;
+
+/// An operation on an undirected graph.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Operation<T> {
+    AddEdge(T, T),
+    /// Remove one copy of an edge. Removing an edge that does not exist does
+    /// nothing.
+    RemoveEdge(T, T),
+    /// Ask whether two vertices are connected.
+    Query(T, T),
+}
+
+/// Answer every query of `operations`, which are applied in order to an
+/// initially empty graph. Vertices exist as soon as they occur in any
+/// operation, and an edge may be added several times.
+///
+/// Every edge is alive during an interval of queries, which is split over the
+/// nodes of a segment tree on the queries. The tree is traversed depth-first,
+/// merging the edges of a node on the way down and undoing the merges on the
+/// way up with [`RollbackDisjointSets`]. This takes `O(n log n log m)` time for
+/// `n` operations on `m` vertices.
+pub fn offline_connectivity<T>(operations: impl IntoIterator<Item = Operation<T>>) -> Vec<bool>
+where
+    T: Eq + Hash,
+{
+    let mut indices = HashMap::new();
+    let mut index_of = |item: T| {
+        let len = indices.len();
+        *indices.entry(item).or_insert(len)
+    };
+    let mut edge_of = |x: T, y: T| {
+        let (x, y) = (index_of(x), index_of(y));
+        (x.min(y), x.max(y))
+    };
+
+    // The first query during which each copy of an edge is alive, and the
+    // alive intervals of removed copies.
+    let mut added: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    let mut intervals = Vec::new();
+    let mut queries = Vec::new();
+    for operation in operations {
+        match operation {
+            Operation::AddEdge(x, y) => {
+                added.entry(edge_of(x, y)).or_default().push(queries.len());
+            }
+            Operation::RemoveEdge(x, y) => {
+                let edge = edge_of(x, y);
+                if let Some(start) = added.get_mut(&edge).and_then(Vec::pop) {
+                    intervals.push((start, queries.len(), edge));
+                }
+            }
+            Operation::Query(x, y) => queries.push(edge_of(x, y)),
+        }
+    }
+    for (edge, starts) in added {
+        intervals.extend(starts.into_iter().map(|start| (start, queries.len(), edge)));
+    }
+
+    if queries.is_empty() {
+        return Vec::new();
+    }
+    let mut sets = RollbackDisjointSets::new();
+    for index in 0..indices.len() {
+        sets.make_set(index).unwrap();
+    }
+
+    let mut tree = SegmentTree::new(queries.len());
+    for (start, end, edge) in intervals {
+        tree.insert(start, end, edge);
+    }
+    let mut answers = Vec::with_capacity(queries.len());
+    tree.answer(&mut sets, &queries, &mut answers);
+    answers
+}
+
+/// A segment tree over the queries `0..len`, in which every node holds the
+/// edges alive during all of its queries.
+struct SegmentTree {
+    len: usize,
+    edges: Vec<Vec<(usize, usize)>>,
+}
+
+impl SegmentTree {
+    fn new(len: usize) -> Self {
+        SegmentTree {
+            len,
+            edges: vec![Vec::new(); 4 * len],
+        }
+    }
+
+    /// Add `edge` to the nodes covering the queries `start..end`.
+    fn insert(&mut self, start: usize, end: usize, edge: (usize, usize)) {
+        if start < end {
+            self.insert_into(1, 0, self.len, start, end, edge);
+        }
+    }
+
+    fn insert_into(
+        &mut self,
+        node: usize,
+        node_start: usize,
+        node_end: usize,
+        start: usize,
+        end: usize,
+        edge: (usize, usize),
+    ) {
+        if start <= node_start && node_end <= end {
+            self.edges[node].push(edge);
+            return;
+        }
+
+        let mid = (node_start + node_end) / 2;
+        if start < mid {
+            self.insert_into(2 * node, node_start, mid, start, end, edge);
+        }
+        if mid < end {
+            self.insert_into(2 * node + 1, mid, node_end, start, end, edge);
+        }
+    }
+
+    /// Answer `queries` in order, appending the answers to `answers`.
+    fn answer(
+        &self,
+        sets: &mut RollbackDisjointSets<usize>,
+        queries: &[(usize, usize)],
+        answers: &mut Vec<bool>,
+    ) {
+        self.answer_in(1, 0, self.len, sets, queries, answers);
+    }
+
+    fn answer_in(
+        &self,
+        node: usize,
+        node_start: usize,
+        node_end: usize,
+        sets: &mut RollbackDisjointSets<usize>,
+        queries: &[(usize, usize)],
+        answers: &mut Vec<bool>,
+    ) {
+        let snapshot = sets.checkpoint();
+        for (x, y) in &self.edges[node] {
+            sets.union(x, y).unwrap();
+        }
+
+        if node_end - node_start == 1 {
+            let (x, y) = queries[node_start];
+            answers.push(sets.same_set(&x, &y).unwrap());
+        } else {
+            let mid = (node_start + node_end) / 2;
+            self.answer_in(2 * node, node_start, mid, sets, queries, answers);
+            self.answer_in(2 * node + 1, mid, node_end, sets, queries, answers);
+        }
+
+        sets.rollback_to(snapshot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disjoint_sets::DisjointSets;
+    use crate::test_rng::TestRng;
+
+    use Operation::{AddEdge, Query, RemoveEdge};
+
+    #[test]
+    fn test_offline_connectivity() {
+        let operations = [
+            Query("a", "b"),
+            AddEdge("a", "b"),
+            AddEdge("b", "c"),
+            Query("a", "c"),
+            AddEdge("c", "b"),
+            RemoveEdge("b", "c"),
+            Query("c", "a"),
+            RemoveEdge("c", "b"),
+            Query("a", "c"),
+            Query("a", "b"),
+            RemoveEdge("x", "y"),
+            Query("x", "x"),
+            Query("x", "y"),
+        ];
+        assert_eq!(
+            offline_connectivity(operations),
+            [false, true, true, false, true, true, false]
+        );
+        assert!(offline_connectivity([AddEdge(1, 2)]).is_empty());
+    }
+
+    #[test]
+    fn test_random_operations() {
+        let mut rng = TestRng::new();
+        let mut next = |n: u64| rng.below(n);
+
+        let mut edges: Vec<(u64, u64)> = Vec::new();
+        let mut operations = Vec::new();
+        let mut expected = Vec::new();
+        for _ in 0..2000 {
+            let (x, y) = (next(30), next(30));
+            match next(3) {
+                0 => {
+                    edges.push((x, y));
+                    operations.push(AddEdge(x, y));
+                }
+                1 if !edges.is_empty() => {
+                    let (x, y) = edges.swap_remove(next(edges.len() as u64) as usize);
+                    operations.push(RemoveEdge(y, x));
+                }
+                _ => {
+                    let mut sets: DisjointSets<u64> = (0..30).collect();
+                    for (x, y) in &edges {
+                        sets.union(x, y).unwrap();
+                    }
+                    expected.push(sets.same_set(&x, &y).unwrap());
+                    operations.push(Query(x, y));
+                }
+            }
+        }
+        assert_eq!(offline_connectivity(operations), expected);
+    }
+}
@@ -0,0 +1,51 @@
+/// How `find` shortens the path from an item to its representative.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// Point every node on the path at the representative.
+    Full,
+    /// Point every other node on the path at its grandparent.
+    Halving,
+    /// Point every node on the path at its grandparent.
+    Splitting,
+    /// Leave the path unchanged, e.g. so that unions can be undone.
+    None,
+}
+
+/// Selects the path compression strategy of disjoint sets at compile time.
+/// All strategies are iterative, so deep trees cannot overflow the stack.
+pub trait Compression {
+    const STRATEGY: Strategy;
+}
+
+/// Full path compression with two passes over the path.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FullCompression;
+
+impl Compression for FullCompression {
+    const STRATEGY: Strategy = Strategy::Full;
+}
+
+/// Path halving in a single pass.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PathHalving;
+
+impl Compression for PathHalving {
+    const STRATEGY: Strategy = Strategy::Halving;
+}
+
+/// Path splitting in a single pass.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PathSplitting;
+
+impl Compression for PathSplitting {
+    const STRATEGY: Strategy = Strategy::Splitting;
+}
+
+/// No path compression, as used by
+/// [`RollbackDisjointSets`](crate::rollback_disjoint_sets::RollbackDisjointSets).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NoCompression;
+
+impl Compression for NoCompression {
+    const STRATEGY: Strategy = Strategy::None;
+}
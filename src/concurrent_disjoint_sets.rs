@@ -0,0 +1,350 @@
+// Loom replaces the atomics to check every interleaving of the tests in
+// `loom_tests`, which run with `RUSTFLAGS="--cfg loom"`.
+#[cfg(all(test, loom))]
+use loom::sync::atomic::{AtomicU64, Ordering};
+#[cfg(not(all(test, loom)))]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::union_find::{Arg, Error, Result};
+
+/// Thread-safe disjoint sets over the dense indices `0..len` that implements
+/// lock-free union-find with union by rank and path halving, in the style of
+/// Anderson and Woll.
+///
+/// All operations take `&self`. The parent and rank of an index are packed
+/// into one atomic word, so that a representative is linked by a single
+/// compare-and-swap that fails if it stopped being a representative or its
+/// rank changed. Representatives are ordered by `(rank, index)` and always
+/// linked from the smaller to the larger one, so concurrent unions can never
+/// form a cycle. `find` shortcuts pointers with compare-and-swap as well,
+/// which only ever points an index at another member of the same set.
+#[derive(Debug, Default)]
+pub struct ConcurrentDisjointSets {
+    /// The parent in the low and the rank in the high 32 bits. An index is
+    /// the representative of its set if its parent is itself. Ranks only
+    /// change while an index is a representative.
+    words: Vec<AtomicU64>,
+}
+
+fn pack(parent: u32, rank: u32) -> u64 {
+    (rank as u64) << 32 | parent as u64
+}
+
+fn parent_of(word: u64) -> u32 {
+    word as u32
+}
+
+fn rank_of(word: u64) -> u32 {
+    (word >> 32) as u32
+}
+
+impl ConcurrentDisjointSets {
+    /// Create `len` singleton sets for the indices `0..len`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` does not fit in a `u32`.
+    pub fn new(len: usize) -> Self {
+        let len = u32::try_from(len).expect("too many items");
+        ConcurrentDisjointSets {
+            words: (0..len).map(|i| AtomicU64::new(pack(i, 0))).collect(),
+        }
+    }
+
+    pub fn contains(&self, item: u32) -> bool {
+        (item as usize) < self.words.len()
+    }
+
+    pub fn num_items(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Find the current representative of the set containing `item`. If
+    /// `item` does not exist in the disjoint sets, an error is returned.
+    ///
+    /// The representative may change as soon as another thread performs a
+    /// union.
+    pub fn find(&self, item: u32) -> Result<u32> {
+        Ok(self.find_repr(self.check(item, Arg::Item)?))
+    }
+
+    /// Check if two items are in the same set. If `x` or `y` do not exist in
+    /// the disjoint sets, an error is returned.
+    pub fn same_set(&self, x: u32, y: u32) -> Result<bool> {
+        let (mut x, mut y) = (self.check(x, Arg::X)?, self.check(y, Arg::Y)?);
+        loop {
+            x = self.find_repr(x);
+            y = self.find_repr(y);
+            if x == y {
+                return Ok(true);
+            }
+            // If `x` is still a representative, `x` and `y` were in
+            // different sets at the moment it was observed. Otherwise a
+            // concurrent union moved it and the check is retried.
+            if parent_of(self.word(x)) == x {
+                return Ok(false);
+            }
+        }
+    }
+
+    /// Merge the sets containing `x` and `y`. Returns whether the sets were
+    /// different before the call. If `x` or `y` do not exist in the disjoint
+    /// sets, an error is returned.
+    pub fn union(&self, x: u32, y: u32) -> Result<bool> {
+        let (mut x, mut y) = (self.check(x, Arg::X)?, self.check(y, Arg::Y)?);
+        loop {
+            x = self.find_repr(x);
+            y = self.find_repr(y);
+            if x == y {
+                return Ok(false);
+            }
+
+            let x_word = self.word(x);
+            let y_word = self.word(y);
+            // Either may have been linked since `find_repr` returned.
+            if parent_of(x_word) != x || parent_of(y_word) != y {
+                continue;
+            }
+
+            let x_rank = rank_of(x_word);
+            let y_rank = rank_of(y_word);
+            let (child, child_word, parent, parent_rank) = if (x_rank, x) < (y_rank, y) {
+                (x, x_word, y, y_rank)
+            } else {
+                (y, y_word, x, x_rank)
+            };
+            if self.words[child as usize]
+                .compare_exchange(
+                    child_word,
+                    pack(parent, rank_of(child_word)),
+                    Ordering::SeqCst,
+                    Ordering::SeqCst,
+                )
+                .is_err()
+            {
+                continue;
+            }
+
+            if rank_of(child_word) == parent_rank {
+                // Failure means `parent` was linked or had its rank bumped
+                // by another thread. Ranks only guide balancing, so the
+                // partition is correct either way.
+                let _ = self.words[parent as usize].compare_exchange(
+                    pack(parent, parent_rank),
+                    pack(parent, parent_rank + 1),
+                    Ordering::SeqCst,
+                    Ordering::SeqCst,
+                );
+            }
+            return Ok(true);
+        }
+    }
+
+    /// Count the sets. The result is only exact if no union runs
+    /// concurrently.
+    pub fn num_sets(&self) -> usize {
+        (0..self.words.len() as u32)
+            .filter(|i| parent_of(self.word(*i)) == *i)
+            .count()
+    }
+
+    fn check(&self, item: u32, arg: Arg) -> Result<u32> {
+        if self.contains(item) {
+            Ok(item)
+        } else {
+            Err(Error::item_not_found(arg).with_item(&item))
+        }
+    }
+
+    fn word(&self, item: u32) -> u64 {
+        self.words[item as usize].load(Ordering::SeqCst)
+    }
+
+    /// Find the representative of the set containing `item`, performing path
+    /// halving along the way.
+    ///
+    /// Assumes `item` exists.
+    fn find_repr(&self, mut item: u32) -> u32 {
+        loop {
+            let word = self.word(item);
+            let parent = parent_of(word);
+            if parent == item {
+                return item;
+            }
+            let grandparent = parent_of(self.word(parent));
+            if grandparent != parent {
+                // Failure means another thread already changed the pointer,
+                // which is fine.
+                let _ = self.words[item as usize].compare_exchange(
+                    word,
+                    pack(grandparent, rank_of(word)),
+                    Ordering::SeqCst,
+                    Ordering::SeqCst,
+                );
+            }
+            item = grandparent;
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::disjoint_sets::DisjointSets;
+    use crate::test_rng::TestRng;
+    use crate::union_find::UnionFind;
+
+    #[test]
+    fn test_concurrent_union_find() {
+        let sets = ConcurrentDisjointSets::new(5);
+        assert!(matches!(sets.find(5), Err(Error::ItemNotFound { .. })));
+        assert!(matches!(sets.union(0, 5), Err(Error::ItemNotFound { .. })));
+
+        // (0, 1, 2), (3, 4)
+        assert!(sets.union(0, 1).unwrap());
+        assert!(sets.union(2, 1).unwrap());
+        assert!(!sets.union(0, 2).unwrap());
+        assert!(sets.union(3, 4).unwrap());
+        assert!(sets.same_set(0, 2).unwrap());
+        assert!(!sets.same_set(2, 3).unwrap());
+        assert_eq!(sets.find(0).unwrap(), sets.find(2).unwrap());
+        assert_eq!(sets.num_sets(), 2);
+    }
+
+    #[test]
+    fn test_concurrent_unions_from_many_threads() {
+        const N: u32 = 10_000;
+        const THREADS: u32 = 8;
+
+        // Every thread links `i` to `i + 2` for its own share of `i`, leaving
+        // the even and the odd indices as two sets.
+        let sets = ConcurrentDisjointSets::new(N as usize);
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                let sets = &sets;
+                s.spawn(move || {
+                    for i in (t..N - 2).step_by(THREADS as usize) {
+                        sets.union(i, i + 2).unwrap();
+                        assert!(sets.same_set(i, i + 2).unwrap());
+                    }
+                });
+            }
+        });
+
+        assert_eq!(sets.num_sets(), 2);
+        assert!(sets.same_set(0, N - 2).unwrap());
+        assert!(sets.same_set(1, N - 1).unwrap());
+        assert!(!sets.same_set(0, 1).unwrap());
+    }
+
+    #[test]
+    fn test_agrees_with_disjoint_sets() {
+        const N: u32 = 2_000;
+        const THREADS: usize = 4;
+
+        // A fixed pseudo-random sparse graph, so that both small and large
+        // components show up.
+        let mut rng = TestRng::new();
+        let edges: Vec<(u32, u32)> = (0..N)
+            .map(|_| (rng.below(N as u64) as u32, rng.below(N as u64) as u32))
+            .collect();
+
+        let concurrent = ConcurrentDisjointSets::new(N as usize);
+        thread::scope(|s| {
+            for chunk in edges.chunks(edges.len() / THREADS) {
+                let concurrent = &concurrent;
+                s.spawn(move || {
+                    for (x, y) in chunk {
+                        concurrent.union(*x, *y).unwrap();
+                    }
+                });
+            }
+        });
+
+        let mut sequential = DisjointSets::new();
+        for i in 0..N {
+            sequential.make_set(i).unwrap();
+        }
+        for (x, y) in &edges {
+            sequential.union(x, y).unwrap();
+        }
+
+        assert_eq!(concurrent.num_sets(), sequential.num_sets());
+        for i in 0..N {
+            let repr = *sequential.find(&i).unwrap();
+            assert!(concurrent.same_set(i, repr).unwrap());
+            assert_eq!(concurrent.find(i).unwrap(), concurrent.find(repr).unwrap());
+        }
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use loom::sync::Arc;
+    use loom::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_crossing_unions() {
+        // Both threads try to link the same two representatives, in opposite
+        // directions. Exactly one of them succeeds, and no cycle is formed.
+        loom::model(|| {
+            let sets = Arc::new(ConcurrentDisjointSets::new(2));
+            let other = Arc::clone(&sets);
+            let handle = thread::spawn(move || other.union(1, 0).unwrap());
+            let merged = sets.union(0, 1).unwrap();
+            assert!(merged ^ handle.join().unwrap());
+
+            assert_eq!(sets.num_sets(), 1);
+            assert!(sets.same_set(0, 1).unwrap());
+            assert_eq!(sets.find(0).unwrap(), sets.find(1).unwrap());
+        });
+    }
+
+    #[test]
+    fn test_crossing_unions_of_merged_sets() {
+        // (0, 1) and (2, 3) are joined by two edges across them at once.
+        loom::model(|| {
+            let sets = Arc::new(ConcurrentDisjointSets::new(4));
+            sets.union(0, 1).unwrap();
+            sets.union(2, 3).unwrap();
+            let other = Arc::clone(&sets);
+            let handle = thread::spawn(move || other.union(2, 1).unwrap());
+            let merged = sets.union(0, 3).unwrap();
+            assert!(merged ^ handle.join().unwrap());
+
+            assert_eq!(sets.num_sets(), 1);
+            let repr = sets.find(0).unwrap();
+            assert!((1..4).all(|i| sets.find(i).unwrap() == repr));
+        });
+    }
+
+    #[test]
+    fn test_union_racing_find() {
+        loom::model(|| {
+            let sets = Arc::new(ConcurrentDisjointSets::new(3));
+            let other = Arc::clone(&sets);
+            let handle = thread::spawn(move || {
+                // Whatever the interleaving, the answers are consistent with
+                // the state before or after the union, and never merge 2.
+                // Once 0 has been seen under 1, the union is visible.
+                let repr = other.find(0).unwrap();
+                assert!(repr == 0 || repr == 1);
+                let same = other.same_set(0, 1).unwrap();
+                assert!(!other.same_set(1, 2).unwrap());
+                if repr == 1 {
+                    assert!(same);
+                }
+            });
+            assert!(sets.union(0, 1).unwrap());
+            assert!(sets.same_set(1, 0).unwrap());
+            handle.join().unwrap();
+
+            assert_eq!(sets.num_sets(), 2);
+            assert!(!sets.same_set(0, 2).unwrap());
+        });
+    }
+}
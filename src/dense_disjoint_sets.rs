@@ -0,0 +1,199 @@
+use crate::union_find::{Arg, Error, Result, UnionFind};
+
+/// Disjoint sets over the dense indices `0..len` that implements union-find
+/// with path compression and union by size.
+///
+/// Parents and sizes are kept in contiguous arrays, so no hashing is done.
+/// Items are `u32` to match the width of the arrays. Since indices are dense,
+/// `make_set` only accepts the next index `len`. Use `grow` to add many
+/// items at once.
+#[derive(Clone, Debug, Default)]
+pub struct DenseDisjointSets {
+    /// An index is the representative of its set if its parent is itself.
+    parent: Vec<u32>,
+    /// Only meaningful for representatives.
+    size: Vec<u32>,
+}
+
+impl DenseDisjointSets {
+    pub fn new() -> Self {
+        DenseDisjointSets {
+            parent: Vec::new(),
+            size: Vec::new(),
+        }
+    }
+
+    /// Create empty disjoint sets with space for at least `capacity` items.
+    pub fn with_capacity(capacity: usize) -> Self {
+        DenseDisjointSets {
+            parent: Vec::with_capacity(capacity),
+            size: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Add singleton sets for the indices `len..new_len`. Does nothing if
+    /// there are already `new_len` items.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new_len` does not fit in a `u32`.
+    pub fn grow(&mut self, new_len: usize) {
+        let new_len = u32::try_from(new_len).expect("too many items");
+        self.parent.extend(self.parent.len() as u32..new_len);
+        self.size.resize(self.parent.len(), 1);
+    }
+
+    pub fn contains(&self, item: &u32) -> bool {
+        (*item as usize) < self.parent.len()
+    }
+
+    pub fn set_size(&mut self, item: &u32) -> Result<usize> {
+        let repr = self.find_repr(self.check(*item, Arg::Item)?);
+        Ok(self.size[repr as usize] as usize)
+    }
+
+    pub fn num_sets(&self) -> usize {
+        self.parent
+            .iter()
+            .enumerate()
+            .filter(|(i, p)| *i == **p as usize)
+            .count()
+    }
+
+    pub fn num_items(&self) -> usize {
+        self.parent.len()
+    }
+
+    fn check(&self, item: u32, arg: Arg) -> Result<u32> {
+        if self.contains(&item) {
+            Ok(item)
+        } else {
+            Err(Error::item_not_found(arg).with_item(&item))
+        }
+    }
+
+    /// Find the representative of the set containing `item`, performing path
+    /// compression along the way.
+    ///
+    /// Assumes `item` exists.
+    fn find_repr(&mut self, item: u32) -> u32 {
+        let mut repr = item;
+        while self.parent[repr as usize] != repr {
+            repr = self.parent[repr as usize];
+        }
+
+        let mut current = item;
+        while current != repr {
+            let next = self.parent[current as usize];
+            self.parent[current as usize] = repr;
+            current = next;
+        }
+        repr
+    }
+}
+
+impl UnionFind<u32> for DenseDisjointSets {
+    fn same_set(&mut self, x: &u32, y: &u32) -> Result<bool> {
+        let x = self.check(*x, Arg::X)?;
+        let y = self.check(*y, Arg::Y)?;
+        Ok(self.find_repr(x) == self.find_repr(y))
+    }
+
+    fn find(&mut self, item: &u32) -> Result<&u32> {
+        let repr = self.find_repr(self.check(*item, Arg::Item)?);
+        // The parent of a representative is itself.
+        Ok(&self.parent[repr as usize])
+    }
+
+    /// Indices are dense, so if `item` is not the next index `len`, or is
+    /// `u32::MAX`, [`Error::ItemRejected`] is returned.
+    fn make_set(&mut self, item: u32) -> Result<()> {
+        if self.contains(&item) {
+            return Err(Error::item_exists().with_item(&item));
+        }
+        if item as usize != self.parent.len() || item == u32::MAX {
+            return Err(Error::item_rejected().with_item(&item));
+        }
+
+        self.parent.push(item);
+        self.size.push(1);
+        Ok(())
+    }
+
+    fn union(&mut self, x: &u32, y: &u32) -> Result<()> {
+        let x = self.check(*x, Arg::X)?;
+        let y = self.check(*y, Arg::Y)?;
+        let x_repr = self.find_repr(x);
+        let y_repr = self.find_repr(y);
+
+        if x_repr == y_repr {
+            return Ok(());
+        }
+
+        let (child, parent) = if self.size[x_repr as usize] < self.size[y_repr as usize] {
+            (x_repr, y_repr)
+        } else {
+            (y_repr, x_repr)
+        };
+        self.parent[child as usize] = parent;
+        self.size[parent as usize] += self.size[child as usize];
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dense_union_find() {
+        let mut sets = DenseDisjointSets::with_capacity(8);
+        assert_eq!(sets.num_items(), 0);
+        assert!(!sets.contains(&0));
+
+        sets.grow(4);
+        assert_eq!(sets.num_items(), 4);
+        assert_eq!(sets.num_sets(), 4);
+        assert!(matches!(sets.make_set(3), Err(Error::ItemExists { .. })));
+        assert!(matches!(
+            sets.union(&0, &4),
+            Err(Error::ItemNotFound { .. })
+        ));
+
+        // Only the next index can be created.
+        assert!(matches!(sets.make_set(5), Err(Error::ItemRejected { .. })));
+        assert!(matches!(
+            sets.make_set(u32::MAX),
+            Err(Error::ItemRejected { .. })
+        ));
+        assert_eq!(sets.num_items(), 4);
+        sets.make_set(4).unwrap();
+        sets.make_set(5).unwrap();
+        assert_eq!(sets.num_items(), 6);
+
+        // (0, 1, 2), (3, 4), (5)
+        sets.union(&0, &1).unwrap();
+        sets.union(&2, &1).unwrap();
+        sets.union(&4, &3).unwrap();
+        assert!(sets.same_set(&0, &2).unwrap());
+        assert!(sets.same_set(&3, &4).unwrap());
+        assert!(!sets.same_set(&2, &3).unwrap());
+        assert_eq!(sets.set_size(&1).unwrap(), 3);
+        assert_eq!(sets.set_size(&3).unwrap(), 2);
+        assert_eq!(sets.set_size(&5).unwrap(), 1);
+        assert_eq!(sets.num_sets(), 3);
+        assert_eq!(*sets.find(&2).unwrap(), 0);
+        assert_eq!(*sets.find(&5).unwrap(), 5);
+
+        // (0, 1, 2, 3, 4), (5)
+        sets.union(&3, &2).unwrap();
+        assert_eq!(sets.set_size(&4).unwrap(), 5);
+        assert_eq!(*sets.find(&4).unwrap(), 0);
+        assert_eq!(sets.num_sets(), 2);
+
+        // Growing to a smaller length is a no-op.
+        sets.grow(2);
+        assert_eq!(sets.num_items(), 6);
+    }
+}
@@ -1,139 +1,957 @@
-use std::collections::HashMap;
+use std::collections::hash_map;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::marker::PhantomData;
 
+use crate::compression::{Compression, FullCompression, Strategy};
+use crate::link::{link_roots, BySize, LinkPolicy, Root};
 use crate::node::Node;
-use crate::union_find::{Error, Result, UnionFind};
+use crate::observer::{NoObserver, UnionObserver};
+use crate::set_stats::SetStats;
+use crate::union_find::{Arg, Error, Result, UnionFind};
 
 // Store IDs in the disjoint sets instead of the items to workaround mutability
 // issues with `Cell`.
-type Id = u64;
+pub(crate) type Id = u64;
 
-/// Disjoint sets data structure that implements union-find with
-/// path compression and union by rank.
+/// A compact representation of [`DisjointSets`] for serialization: every
+/// item, and for each item the index of its parent in `items`.
+/// Representatives are their own parent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DisjointSetsParts<T> {
+    pub items: Vec<T>,
+    pub parents: Vec<usize>,
+}
+
+/// Disjoint sets data structure that implements union-find with linking by
+/// the policy `L` and path compression by the strategy `C`, which default to
+/// union by size and full path compression. Changes are reported to the
+/// observer `O`, which defaults to none.
+///
+/// Nodes are updated through `Cell`s during lookups, so `DisjointSets` is not
+/// `Sync`. Use [`ConcurrentDisjointSets`] to share disjoint sets between
+/// threads.
+///
+/// [`ConcurrentDisjointSets`]: crate::concurrent_disjoint_sets::ConcurrentDisjointSets
 #[derive(Clone, Debug, Default)]
-pub struct DisjointSets<T> {
+pub struct DisjointSets<T, L = BySize, C = FullCompression, O = NoObserver> {
     nodes: HashMap<Id, Node<Id>>,
     item_to_id: HashMap<T, Id>,
+    id_to_item: HashMap<Id, T>,
     next_id: Id,
+    stats: SetStats,
+    link_policy: L,
+    compression: PhantomData<C>,
+    observer: O,
 }
 
 impl<T> DisjointSets<T>
 where
-    T: Eq + Hash,
+    T: Eq + Hash + Clone,
 {
     pub fn new() -> Self {
+        Self::with_link_policy(BySize)
+    }
+
+    /// Create empty disjoint sets with space for at least `capacity` items.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut sets = Self::new();
+        sets.reserve(capacity);
+        sets
+    }
+
+    /// Create disjoint sets from pairs of items to merge. Items are added as
+    /// needed, so every item occurring in `edges` ends up in the sets.
+    pub fn from_edges(edges: impl IntoIterator<Item = (T, T)>) -> Self {
+        let mut sets = Self::new();
+        for (x, y) in edges {
+            sets.union_or_insert(x, y);
+        }
+        sets
+    }
+
+    /// Rebuild disjoint sets from their compact representation. See
+    /// [`DisjointSets::from_parts_with_link_policy`].
+    pub fn from_parts(parts: DisjointSetsParts<T>) -> Result<Self> {
+        Self::from_parts_with_link_policy(parts, BySize)
+    }
+}
+
+impl<T, L, C> DisjointSets<T, L, C>
+where
+    T: Eq + Hash + Clone,
+    L: LinkPolicy,
+    C: Compression,
+{
+    /// Create empty disjoint sets that link representatives by `link_policy`.
+    pub fn with_link_policy(link_policy: L) -> Self {
         DisjointSets {
             nodes: HashMap::new(),
             item_to_id: HashMap::new(),
+            id_to_item: HashMap::new(),
             next_id: 0,
+            stats: SetStats::new(),
+            link_policy,
+            compression: PhantomData,
+            observer: NoObserver,
         }
     }
 
+    /// Rebuild disjoint sets that link representatives by `link_policy` from
+    /// their compact representation. If the lengths differ, a parent index is
+    /// out of bounds or the parents form a cycle, [`Error::Malformed`] is
+    /// returned. If an item occurs twice, [`Error::ItemExists`] is returned.
+    pub fn from_parts_with_link_policy(
+        parts: DisjointSetsParts<T>,
+        link_policy: L,
+    ) -> Result<Self> {
+        let DisjointSetsParts { items, parents } = parts;
+        if items.len() != parents.len() || parents.iter().any(|p| *p >= parents.len()) {
+            return Err(Error::Malformed);
+        }
+
+        // Follow every parent chain to its root, remembering the roots and
+        // depths of all indices seen so far. Revisiting an index of the
+        // current chain means there is a cycle.
+        const UNVISITED: usize = usize::MAX;
+        const ON_CHAIN: usize = usize::MAX - 1;
+        let mut roots = vec![UNVISITED; parents.len()];
+        let mut depths = vec![0; parents.len()];
+        let mut chain = Vec::new();
+        for start in 0..parents.len() {
+            let mut i = start;
+            while roots[i] == UNVISITED && parents[i] != i {
+                roots[i] = ON_CHAIN;
+                chain.push(i);
+                i = parents[i];
+            }
+            let root = match roots[i] {
+                ON_CHAIN => return Err(Error::Malformed),
+                UNVISITED => i,
+                root => root,
+            };
+            roots[i] = root;
+            let mut depth = depths[i];
+            for j in chain.drain(..).rev() {
+                depth += 1;
+                depths[j] = depth;
+                roots[j] = root;
+            }
+        }
+
+        let mut sets = DisjointSets::with_link_policy(link_policy);
+        for (i, item) in items.into_iter().enumerate() {
+            sets.make_set(item)?;
+            let node = sets.nodes.get(&(i as Id)).unwrap();
+            node.set_parent(parents[i] as Id);
+        }
+        for (i, (root, depth)) in roots.into_iter().zip(depths).enumerate() {
+            let node = sets.nodes.get(&(root as Id)).unwrap();
+            node.set_rank(node.rank().max(depth));
+            if depth > 0 {
+                node.set_size(node.size() + 1);
+                splice(&sets.nodes, root as Id, i as Id);
+            }
+        }
+
+        sets.stats = SetStats::new();
+        for node in sets.nodes.values().filter(|n| n.is_representative()) {
+            sets.stats.add(node.size());
+        }
+
+        Ok(sets)
+    }
+}
+
+impl<T, L, C, O> DisjointSets<T, L, C, O>
+where
+    T: Eq + Hash + Clone,
+    L: LinkPolicy,
+    C: Compression,
+    O: UnionObserver<T>,
+{
+    /// Report all further changes to `observer`, which is not told about the
+    /// existing items and sets.
+    pub fn with_observer<P>(self, observer: P) -> DisjointSets<T, L, C, P>
+    where
+        P: UnionObserver<T>,
+    {
+        DisjointSets {
+            nodes: self.nodes,
+            item_to_id: self.item_to_id,
+            id_to_item: self.id_to_item,
+            next_id: self.next_id,
+            stats: self.stats,
+            link_policy: self.link_policy,
+            compression: self.compression,
+            observer,
+        }
+    }
+
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    /// Reserve space for at least `additional` more items.
+    pub fn reserve(&mut self, additional: usize) {
+        self.nodes.reserve(additional);
+        self.item_to_id.reserve(additional);
+        self.id_to_item.reserve(additional);
+    }
+
+    /// Add a singleton set for every item of `iter`, stopping at the first
+    /// item that already exists in the disjoint sets with an error. Items
+    /// before it stay added. Use `extend` to skip existing items instead.
+    pub fn try_extend(&mut self, iter: impl IntoIterator<Item = T>) -> Result<()> {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for item in iter {
+            self.make_set(item)?;
+        }
+        Ok(())
+    }
+
     pub fn contains(&self, item: &T) -> bool {
-        self.item_to_id.get(item).is_some()
+        self.item_to_id.contains_key(item)
+    }
+
+    /// Get the entry of `item` for in-place manipulation. The entry is vacant
+    /// if `item` does not exist in the disjoint sets.
+    pub fn entry(&mut self, item: T) -> Entry<'_, T, C, O> {
+        match self.item_to_id.entry(item) {
+            hash_map::Entry::Occupied(entry) => Entry::Occupied(OccupiedEntry {
+                entry,
+                nodes: &self.nodes,
+                id_to_item: &self.id_to_item,
+                compression: PhantomData,
+            }),
+            hash_map::Entry::Vacant(entry) => Entry::Vacant(VacantEntry {
+                entry,
+                nodes: &mut self.nodes,
+                id_to_item: &mut self.id_to_item,
+                next_id: &mut self.next_id,
+                stats: &mut self.stats,
+                observer: &mut self.observer,
+                compression: PhantomData,
+            }),
+        }
+    }
+
+    /// Find the representative of the set containing `item`, first creating
+    /// a singleton set for `item` if it does not exist.
+    pub fn find_or_insert(&mut self, item: T) -> &T {
+        let id = self.id_or_insert(item);
+        let repr = self.find_repr_id(id);
+        self.id_to_item.get(&repr).unwrap()
+    }
+
+    /// Merge the sets containing `x` and `y`, first creating singleton sets
+    /// for those that do not exist.
+    pub fn union_or_insert(&mut self, x: T, y: T) {
+        let x_id = self.id_or_insert(x);
+        let y_id = self.id_or_insert(y);
+        let x_repr = self.find_repr_id(x_id);
+        let y_repr = self.find_repr_id(y_id);
+
+        if x_repr != y_repr {
+            self.link(x_repr, y_repr);
+        }
+    }
+
+    /// Get the ID of `item`, first creating a singleton set for it if it does
+    /// not exist.
+    fn id_or_insert(&mut self, item: T) -> Id {
+        self.entry(item).or_insert().id()
     }
 
     pub fn set_size(&mut self, item: &T) -> Result<usize> {
-        let id = *self.item_to_id.get(item).ok_or(Error::ItemNotFound)?;
+        let id = *self
+            .item_to_id
+            .get(item)
+            .ok_or(Error::item_not_found(Arg::Item))?;
         let repr = self.find_repr_id(id);
         let node = self.nodes.get(&repr).unwrap();
-        Ok(node.rank() as usize)
+        Ok(node.size())
     }
 
+    /// Get the number of sets in constant time.
     pub fn num_sets(&self) -> usize {
+        self.stats.num_sets()
+    }
+
+    /// Get the size of the largest set in constant time, or 0 if there are
+    /// no sets.
+    pub fn largest_set_size(&self) -> usize {
+        self.stats.largest()
+    }
+
+    /// Get the number of sets of each size, for all sizes that occur.
+    pub fn set_size_histogram(&self) -> &BTreeMap<usize, usize> {
+        self.stats.histogram()
+    }
+
+    pub fn num_items(&self) -> usize {
+        self.item_to_id.len()
+    }
+
+    /// Convert the disjoint sets to their compact representation. Items are
+    /// in insertion order and point directly at their representative, except
+    /// that removing a representative moves another item of its set into its
+    /// place.
+    pub fn to_parts(&self) -> DisjointSetsParts<T> {
+        let mut ids: Vec<Id> = self.id_to_item.keys().copied().collect();
+        ids.sort_unstable();
+        let index_of: HashMap<Id, usize> = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+
+        DisjointSetsParts {
+            items: ids.iter().map(|id| self.id_to_item[id].clone()).collect(),
+            parents: ids
+                .iter()
+                .map(|id| index_of[&self.find_repr_id_immutable(*id)])
+                .collect(),
+        }
+    }
+
+    /// Remove `item` from the disjoint sets and return it. The remaining
+    /// members of its set stay connected. If `item` does not exist in the
+    /// disjoint sets, an error is returned.
+    ///
+    /// The node of a removed item stays in its tree until removed items make
+    /// up half of all nodes, and then all trees are rebuilt. This keeps the
+    /// amortized time of a removal close to that of a `find`.
+    pub fn remove(&mut self, item: &T) -> Result<T> {
+        let id = *self
+            .item_to_id
+            .get(item)
+            .ok_or(Error::item_not_found(Arg::Item))?;
+        let repr = self.find_repr_id(id);
+        let repr_node = self.nodes.get(&repr).unwrap();
+        let size = repr_node.size();
+        self.stats.remove(size);
+        if size > 1 {
+            self.stats.add(size - 1);
+            repr_node.set_size(size - 1);
+        }
+
+        // Representatives must keep an item, so a removed representative
+        // takes the item of the next member instead, whose node is removed.
+        let removed = if id == repr && size > 1 {
+            repr_node.next()
+        } else {
+            id
+        };
+        unlink(&self.nodes, removed);
+        self.item_to_id.remove(item);
+        let item = self.id_to_item.remove(&id).unwrap();
+        if removed != id {
+            let moved = self.id_to_item.remove(&removed).unwrap();
+            *self.item_to_id.get_mut(&moved).unwrap() = id;
+            self.id_to_item.insert(id, moved);
+        }
+
+        // A singleton without children can go right away.
+        if removed == repr && self.nodes.get(&repr).unwrap().rank() == 0 {
+            self.nodes.remove(&repr);
+        }
+        if self.nodes.len() > 2 * self.id_to_item.len() {
+            self.rebuild();
+        }
+
+        let new_repr = (removed != id).then(|| self.id_to_item.get(&id).unwrap());
+        self.observer.on_remove(&item, new_repr);
+        Ok(item)
+    }
+
+    /// Drop the nodes of removed items, and point every remaining node
+    /// directly at its representative.
+    fn rebuild(&mut self) {
+        let reprs: Vec<(Id, Id)> = self
+            .id_to_item
+            .keys()
+            .map(|id| (*id, self.find_repr_id_immutable(*id)))
+            .collect();
+        let id_to_item = &self.id_to_item;
+        self.nodes.retain(|id, _| id_to_item.contains_key(id));
+        for (id, repr) in reprs {
+            let node = self.nodes.get(&id).unwrap();
+            node.set_parent(repr);
+            node.set_rank(0);
+            if id != repr {
+                self.nodes.get(&repr).unwrap().set_rank(1);
+            }
+        }
+    }
+
+    /// Find the representative of the set containing `item` without
+    /// performing path compression. If `item` does not exist in the disjoint
+    /// sets, an error is returned.
+    pub fn find_immutable(&self, item: &T) -> Result<&T> {
+        let id = *self
+            .item_to_id
+            .get(item)
+            .ok_or(Error::item_not_found(Arg::Item))?;
+        let repr = self.find_repr_id_immutable(id);
+        Ok(self.id_to_item.get(&repr).unwrap())
+    }
+
+    /// Iterate over the representatives of all sets, in no particular order.
+    pub fn representatives(&self) -> impl Iterator<Item = &T> {
+        // The trees of removed sets can linger until the next rebuild, but
+        // their representatives have no item.
         self.nodes
             .values()
             .filter(|n| n.is_representative())
-            .count()
+            .filter_map(|n| self.id_to_item.get(&n.item()))
     }
 
-    pub fn num_items(&self) -> usize {
-        self.item_to_id.len()
+    /// Iterate over all sets, each given as the list of its members. Neither
+    /// the sets nor the members within a set are in any particular order.
+    pub fn sets(&self) -> impl Iterator<Item = Vec<&T>> {
+        let mut sets: HashMap<Id, Vec<&T>> = HashMap::new();
+        for (id, item) in &self.id_to_item {
+            let repr = self.find_repr_id_immutable(*id);
+            sets.entry(repr).or_default().push(item);
+        }
+        sets.into_values()
+    }
+
+    /// Get all members of the set containing `item`, including `item` itself.
+    /// If `item` does not exist in the disjoint sets, an error is returned.
+    pub fn members(&self, item: &T) -> Result<Vec<&T>> {
+        let id = *self
+            .item_to_id
+            .get(item)
+            .ok_or(Error::item_not_found(Arg::Item))?;
+        let mut members = vec![self.id_to_item.get(&id).unwrap()];
+        let mut member = self.nodes.get(&id).unwrap().next();
+        while member != id {
+            members.push(self.id_to_item.get(&member).unwrap());
+            member = self.nodes.get(&member).unwrap().next();
+        }
+        Ok(members)
     }
 }
 
-impl<T> UnionFind<T> for DisjointSets<T>
+impl<T, L, C, O> UnionFind<T> for DisjointSets<T, L, C, O>
 where
-    T: Eq + Hash,
+    T: Eq + Hash + Clone,
+    L: LinkPolicy,
+    C: Compression,
+    O: UnionObserver<T>,
 {
     fn same_set(&mut self, x: &T, y: &T) -> Result<bool> {
-        let x_id = *self.item_to_id.get(x).ok_or(Error::ItemNotFound)?;
-        let y_id = *self.item_to_id.get(y).ok_or(Error::ItemNotFound)?;
+        let x_id = *self
+            .item_to_id
+            .get(x)
+            .ok_or(Error::item_not_found(Arg::X))?;
+        let y_id = *self
+            .item_to_id
+            .get(y)
+            .ok_or(Error::item_not_found(Arg::Y))?;
         let x_repr = self.find_repr_id(x_id);
         let y_repr = self.find_repr_id(y_id);
         Ok(x_repr == y_repr)
     }
 
+    fn find(&mut self, item: &T) -> Result<&T> {
+        let id = *self
+            .item_to_id
+            .get(item)
+            .ok_or(Error::item_not_found(Arg::Item))?;
+        let repr = self.find_repr_id(id);
+        Ok(self.id_to_item.get(&repr).unwrap())
+    }
+
     fn make_set(&mut self, item: T) -> Result<()> {
-        if self.contains(&item) {
-            return Err(Error::ItemExists);
+        match self.entry(item) {
+            Entry::Occupied(_) => Err(Error::item_exists()),
+            Entry::Vacant(entry) => {
+                entry.insert();
+                Ok(())
+            }
         }
-
-        let id = self.next_id;
-        self.next_id += 1;
-        self.item_to_id.insert(item, id);
-
-        self.nodes.insert(id, Node::new(id));
-        Ok(())
     }
 
     fn union(&mut self, x: &T, y: &T) -> Result<()> {
-        let x_id = *self.item_to_id.get(x).ok_or(Error::ItemNotFound)?;
-        let y_id = *self.item_to_id.get(y).ok_or(Error::ItemNotFound)?;
+        let x_id = *self
+            .item_to_id
+            .get(x)
+            .ok_or(Error::item_not_found(Arg::X))?;
+        let y_id = *self
+            .item_to_id
+            .get(y)
+            .ok_or(Error::item_not_found(Arg::Y))?;
         let x_repr = self.find_repr_id(x_id);
         let y_repr = self.find_repr_id(y_id);
 
-        if x_repr == y_repr {
-            return Ok(());
+        if x_repr != y_repr {
+            self.link(x_repr, y_repr);
         }
 
-        let x_node = self.nodes.get(&x_repr).unwrap();
-        let y_node = self.nodes.get(&y_repr).unwrap();
-        let rank_sum = x_node.rank() + y_node.rank();
+        Ok(())
+    }
+}
 
-        if x_node.rank() < y_node.rank() {
-            x_node.set_parent(y_repr);
-            y_node.set_rank(rank_sum);
-        } else {
-            y_node.set_parent(x_repr);
-            x_node.set_rank(rank_sum);
+/// Variants of the [`UnionFind`] methods whose errors include the offending
+/// item, as added by [`Error::with_item`].
+impl<T, L, C, O> DisjointSets<T, L, C, O>
+where
+    T: Eq + Hash + Clone + Debug,
+    L: LinkPolicy,
+    C: Compression,
+    O: UnionObserver<T>,
+{
+    pub fn same_set_with_items(&mut self, x: &T, y: &T) -> Result<bool> {
+        self.same_set(x, y).map_err(|e| with_arg_item(e, x, y))
+    }
+
+    pub fn find_with_items(&mut self, item: &T) -> Result<&T> {
+        self.find(item).map_err(|e| e.with_item(item))
+    }
+
+    pub fn make_set_with_items(&mut self, item: T) -> Result<()> {
+        match self.entry(item) {
+            Entry::Occupied(entry) => Err(Error::item_exists().with_item(entry.item())),
+            Entry::Vacant(entry) => {
+                entry.insert();
+                Ok(())
+            }
         }
+    }
 
-        Ok(())
+    pub fn union_with_items(&mut self, x: &T, y: &T) -> Result<()> {
+        self.union(x, y).map_err(|e| with_arg_item(e, x, y))
+    }
+}
+
+/// Attach `x` or `y` to `error`, depending on which argument it refers to.
+fn with_arg_item<T: Debug>(error: Error, x: &T, y: &T) -> Error {
+    match error {
+        Error::ItemNotFound { arg: Arg::Y, .. } => error.with_item(y),
+        _ => error.with_item(x),
+    }
+}
+
+/// Serialized as [`DisjointSetsParts`].
+#[cfg(feature = "serde")]
+impl<T, L, C, O> serde::Serialize for DisjointSets<T, L, C, O>
+where
+    T: Eq + Hash + Clone + serde::Serialize,
+    L: LinkPolicy,
+    C: Compression,
+    O: UnionObserver<T>,
+{
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        self.to_parts().serialize(serializer)
+    }
+}
+
+/// Deserialized from [`DisjointSetsParts`], which is validated like in
+/// [`DisjointSets::from_parts_with_link_policy`].
+#[cfg(feature = "serde")]
+impl<'de, T, L, C> serde::Deserialize<'de> for DisjointSets<T, L, C>
+where
+    T: Eq + Hash + Clone + serde::Deserialize<'de>,
+    L: LinkPolicy + Default,
+    C: Compression,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let parts = DisjointSetsParts::deserialize(deserializer)?;
+        Self::from_parts_with_link_policy(parts, L::default()).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A view into a single item of [`DisjointSets`], which may or may not exist.
+/// The item is looked up only once, when the entry is created.
+pub enum Entry<'a, T, C = FullCompression, O = NoObserver> {
+    Occupied(OccupiedEntry<'a, T, C>),
+    Vacant(VacantEntry<'a, T, C, O>),
+}
+
+/// A view into an item that exists in [`DisjointSets`].
+pub struct OccupiedEntry<'a, T, C = FullCompression> {
+    entry: hash_map::OccupiedEntry<'a, T, Id>,
+    nodes: &'a HashMap<Id, Node<Id>>,
+    id_to_item: &'a HashMap<Id, T>,
+    compression: PhantomData<C>,
+}
+
+/// A view into an item that does not exist in [`DisjointSets`].
+pub struct VacantEntry<'a, T, C = FullCompression, O = NoObserver> {
+    entry: hash_map::VacantEntry<'a, T, Id>,
+    nodes: &'a mut HashMap<Id, Node<Id>>,
+    id_to_item: &'a mut HashMap<Id, T>,
+    next_id: &'a mut Id,
+    stats: &'a mut SetStats,
+    observer: &'a mut O,
+    compression: PhantomData<C>,
+}
+
+impl<'a, T, C, O> Entry<'a, T, C, O>
+where
+    T: Eq + Hash + Clone,
+    C: Compression,
+    O: UnionObserver<T>,
+{
+    pub fn item(&self) -> &T {
+        match self {
+            Entry::Occupied(entry) => entry.item(),
+            Entry::Vacant(entry) => entry.item(),
+        }
+    }
+
+    /// Create a singleton set for the item if it does not exist.
+    pub fn or_insert(self) -> OccupiedEntry<'a, T, C> {
+        match self {
+            Entry::Occupied(entry) => entry,
+            Entry::Vacant(entry) => entry.insert(),
+        }
+    }
+}
+
+impl<T, C> OccupiedEntry<'_, T, C>
+where
+    C: Compression,
+{
+    pub fn item(&self) -> &T {
+        self.entry.key()
+    }
+
+    /// Find the representative of the set containing the item.
+    pub fn find(&mut self) -> &T {
+        let repr = self.repr();
+        self.id_to_item.get(&repr).unwrap()
+    }
+
+    pub fn set_size(&mut self) -> usize {
+        let repr = self.repr();
+        self.nodes.get(&repr).unwrap().size()
+    }
+
+    fn id(&self) -> Id {
+        *self.entry.get()
+    }
+
+    fn repr(&self) -> Id {
+        find_repr::<C>(self.nodes, self.id(), &mut |_, _| {})
+    }
+}
+
+impl<'a, T, C, O> VacantEntry<'a, T, C, O>
+where
+    T: Eq + Hash + Clone,
+    O: UnionObserver<T>,
+{
+    pub fn item(&self) -> &T {
+        self.entry.key()
+    }
+
+    pub fn into_item(self) -> T {
+        self.entry.into_key()
+    }
+
+    /// Create a singleton set for the item.
+    pub fn insert(self) -> OccupiedEntry<'a, T, C> {
+        let id = *self.next_id;
+        *self.next_id += 1;
+        self.id_to_item.insert(id, self.entry.key().clone());
+        let entry = self.entry.insert_entry(id);
+
+        self.nodes.insert(id, Node::new(id));
+        self.stats.add(1);
+        self.observer.on_make_set(&self.id_to_item[&id]);
+        OccupiedEntry {
+            entry,
+            nodes: self.nodes,
+            id_to_item: self.id_to_item,
+            compression: PhantomData,
+        }
+    }
+}
+
+/// Add a singleton set for every item, skipping items that already exist.
+impl<T, L, C, O> Extend<T> for DisjointSets<T, L, C, O>
+where
+    T: Eq + Hash + Clone,
+    L: LinkPolicy,
+    C: Compression,
+    O: UnionObserver<T>,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for item in iter {
+            self.id_or_insert(item);
+        }
     }
 }
 
-impl<T> DisjointSets<T> {
-    /// Find the representative of the set containing `id`, performing path
-    /// compression along the way.
+/// Create a singleton set for every item, skipping duplicates.
+impl<T, L, C, O> FromIterator<T> for DisjointSets<T, L, C, O>
+where
+    T: Eq + Hash + Clone,
+    L: LinkPolicy + Default,
+    C: Compression,
+    O: UnionObserver<T> + Default,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut sets = DisjointSets::with_link_policy(L::default()).with_observer(O::default());
+        sets.extend(iter);
+        sets
+    }
+}
+
+impl<T, L, C, O> DisjointSets<T, L, C, O>
+where
+    T: Eq + Hash,
+{
+    /// Get the ID of `item`, reporting it as argument `arg` if it does not
+    /// exist.
+    pub(crate) fn id_of(&self, item: &T, arg: Arg) -> Result<Id> {
+        self.item_to_id
+            .get(item)
+            .copied()
+            .ok_or(Error::item_not_found(arg))
+    }
+}
+
+impl<T, L, C, O> DisjointSets<T, L, C, O>
+where
+    T: Eq + Hash,
+    L: LinkPolicy,
+    O: UnionObserver<T>,
+{
+    /// Merge the sets with the distinct representatives `x_repr` and
+    /// `y_repr`, and return the representative of the merged set.
+    pub(crate) fn link(&mut self, x_repr: Id, y_repr: Id) -> Id {
+        let (child, parent) = link_roots(&self.link_policy, self.root(x_repr), self.root(y_repr));
+        let (repr, loser) = (parent.index, child.index);
+        self.nodes.get(&loser).unwrap().set_parent(repr);
+        let repr_node = self.nodes.get(&repr).unwrap();
+        repr_node.set_size(parent.size);
+        repr_node.set_rank(parent.rank);
+        splice(&self.nodes, repr, loser);
+
+        self.stats.remove(child.size);
+        self.stats.remove(parent.size - child.size);
+        self.stats.add(parent.size);
+        self.observer.on_union(
+            &self.id_to_item[&repr],
+            &self.id_to_item[&loser],
+            parent.size,
+        );
+        repr
+    }
+
+    /// Undo the `link` that linked the representative `child` under
+    /// `parent`, whose rank was `parent_rank` before. All later changes to
+    /// the trees must have been undone already.
+    pub(crate) fn unlink(&mut self, child: Id, parent: Id, parent_rank: usize) {
+        let child_node = self.nodes.get(&child).unwrap();
+        let parent_node = self.nodes.get(&parent).unwrap();
+        let (child_size, size) = (child_node.size(), parent_node.size());
+        child_node.set_parent(child);
+        parent_node.set_size(size - child_size);
+        parent_node.set_rank(parent_rank);
+        splice(&self.nodes, parent, child);
+
+        self.stats.remove(size);
+        self.stats.add(size - child_size);
+        self.stats.add(child_size);
+    }
+}
+
+impl<T, L, C, O> DisjointSets<T, L, C, O> {
+    /// Describe the representative `repr` for a link policy.
+    pub(crate) fn root(&self, repr: Id) -> Root {
+        let node = self.nodes.get(&repr).unwrap();
+        Root {
+            index: node.item(),
+            size: node.size(),
+            rank: node.rank(),
+        }
+    }
+}
+
+impl<T, L, C, O> DisjointSets<T, L, C, O>
+where
+    C: Compression,
+{
+    /// Find the representative of the set containing `id`, compressing the
+    /// path along the way.
     ///
     /// Assumes `id` exists.
     fn find_repr_id(&mut self, id: Id) -> Id {
-        let node = self.nodes.get(&id).unwrap();
-        self.find_repr_inner(node)
+        self.find_repr_id_with(id, &mut |_, _| {})
     }
 
-    fn find_repr_inner(&self, node: &Node<Id>) -> Id {
+    /// Like `find_repr_id`, but calls `on_relink(id, old_parent)` whenever
+    /// the parent of `id` is changed from `old_parent` to the parent of
+    /// `old_parent`.
+    pub(crate) fn find_repr_id_with(&self, id: Id, on_relink: &mut impl FnMut(Id, Id)) -> Id {
+        find_repr::<C>(&self.nodes, id, on_relink)
+    }
+}
+
+impl<T, L, C, O> DisjointSets<T, L, C, O> {
+    /// Find the representative of the set containing `id` without modifying
+    /// the tree.
+    ///
+    /// Assumes `id` exists.
+    fn find_repr_id_immutable(&self, id: Id) -> Id {
+        find_repr_immutable(&self.nodes, id)
+    }
+}
+
+// The functions below only need the nodes, so that entries, which borrow the
+// fields of `DisjointSets` separately, can use them too. Parent pointers are
+// cells, so compressing paths does not need a mutable borrow.
+
+fn find_repr<C: Compression>(
+    nodes: &HashMap<Id, Node<Id>>,
+    id: Id,
+    on_relink: &mut impl FnMut(Id, Id),
+) -> Id {
+    match C::STRATEGY {
+        Strategy::Full => find_repr_full(nodes, id, on_relink),
+        Strategy::Halving => find_repr_halving(nodes, id, on_relink),
+        Strategy::Splitting => find_repr_splitting(nodes, id, on_relink),
+        Strategy::None => find_repr_immutable(nodes, id),
+    }
+}
+
+/// Join the circular member lists containing `x` and `y`. If they are in the
+/// same list, it is split between them instead, which undoes joining them.
+fn splice(nodes: &HashMap<Id, Node<Id>>, x: Id, y: Id) {
+    let x_node = nodes.get(&x).unwrap();
+    let y_node = nodes.get(&y).unwrap();
+    let (x_next, y_next) = (x_node.next(), y_node.next());
+    x_node.set_next(y_next);
+    nodes.get(&y_next).unwrap().set_prev(x);
+    y_node.set_next(x_next);
+    nodes.get(&x_next).unwrap().set_prev(y);
+}
+
+/// Take `id` out of its circular member list.
+fn unlink(nodes: &HashMap<Id, Node<Id>>, id: Id) {
+    let node = nodes.get(&id).unwrap();
+    let (prev, next) = (node.prev(), node.next());
+    nodes.get(&prev).unwrap().set_next(next);
+    nodes.get(&next).unwrap().set_prev(prev);
+}
+
+fn parent_of(nodes: &HashMap<Id, Node<Id>>, id: Id) -> Id {
+    nodes.get(&id).unwrap().parent()
+}
+
+fn set_parent_of(nodes: &HashMap<Id, Node<Id>>, id: Id, parent: Id) {
+    nodes.get(&id).unwrap().set_parent(parent);
+}
+
+fn find_repr_immutable(nodes: &HashMap<Id, Node<Id>>, mut id: Id) -> Id {
+    loop {
+        let node = nodes.get(&id).unwrap();
         if node.is_representative() {
-            node.item()
-        } else {
-            let parent = self.nodes.get(&node.parent()).unwrap();
-            let representative = self.find_repr_inner(parent);
-            node.set_parent(representative);
-            representative
+            return id;
         }
+        id = node.parent();
     }
 }
 
-unsafe impl<T> Send for DisjointSets<T> where T: Send {}
-unsafe impl<T> Sync for DisjointSets<T> where T: Sync {}
+fn find_repr_full(nodes: &HashMap<Id, Node<Id>>, id: Id, on_relink: &mut impl FnMut(Id, Id)) -> Id {
+    // Reverse the parent pointers on the way up, so that the path can be
+    // walked back down from the representative without extra memory.
+    // `id` temporarily points at itself to mark the end of the path.
+    let mut prev = id;
+    let mut current = id;
+    loop {
+        let parent = parent_of(nodes, current);
+        if parent == current {
+            break;
+        }
+        set_parent_of(nodes, current, prev);
+        prev = current;
+        current = parent;
+    }
+    let representative = current;
+    if prev == representative {
+        return representative;
+    }
+
+    // Going down, every old parent already points at the representative.
+    let mut old_parent = representative;
+    let mut current = prev;
+    loop {
+        let child = parent_of(nodes, current);
+        set_parent_of(nodes, current, representative);
+        if old_parent != representative {
+            on_relink(current, old_parent);
+        }
+        if current == id {
+            return representative;
+        }
+        old_parent = current;
+        current = child;
+    }
+}
+
+fn find_repr_halving(
+    nodes: &HashMap<Id, Node<Id>>,
+    mut id: Id,
+    on_relink: &mut impl FnMut(Id, Id),
+) -> Id {
+    loop {
+        let parent = parent_of(nodes, id);
+        if parent == id {
+            return id;
+        }
+        let grandparent = parent_of(nodes, parent);
+        if grandparent != parent {
+            set_parent_of(nodes, id, grandparent);
+            on_relink(id, parent);
+        }
+        id = grandparent;
+    }
+}
+
+fn find_repr_splitting(
+    nodes: &HashMap<Id, Node<Id>>,
+    mut id: Id,
+    on_relink: &mut impl FnMut(Id, Id),
+) -> Id {
+    loop {
+        let parent = parent_of(nodes, id);
+        if parent == id {
+            return id;
+        }
+        let grandparent = parent_of(nodes, parent);
+        if grandparent != parent {
+            set_parent_of(nodes, id, grandparent);
+            on_relink(id, parent);
+        }
+        id = parent;
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::compression::{NoCompression, PathHalving, PathSplitting};
+    use crate::link::{ByRandomIndex, ByRank};
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_union_find() {
         let mut sets = DisjointSets::new();
 
@@ -202,4 +1020,630 @@ mod tests {
         assert_eq!(sets.set_size(&5).unwrap(), 5);
         assert_eq!(sets.num_sets(), 1);
     }
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<DisjointSets<String>>();
+    }
+
+    #[test]
+    fn test_find() {
+        let mut sets = DisjointSets::new();
+        for i in 1..=5 {
+            sets.make_set(i).unwrap();
+        }
+
+        assert!(matches!(sets.find(&6), Err(Error::ItemNotFound { .. })));
+        assert!(matches!(
+            sets.find_immutable(&6),
+            Err(Error::ItemNotFound { .. })
+        ));
+        assert_eq!(*sets.find(&1).unwrap(), 1);
+        assert_eq!(*sets.find_immutable(&1).unwrap(), 1);
+
+        // (1, 2, 3), (4, 5)
+        sets.union(&1, &2).unwrap();
+        sets.union(&2, &3).unwrap();
+        sets.union(&4, &5).unwrap();
+
+        let repr = *sets.find_immutable(&3).unwrap();
+        assert!([1, 2, 3].contains(&repr));
+        assert_eq!(*sets.find_immutable(&1).unwrap(), repr);
+        assert_eq!(*sets.find_immutable(&2).unwrap(), repr);
+        assert_eq!(*sets.find(&3).unwrap(), repr);
+        assert_eq!(*sets.find(&1).unwrap(), repr);
+
+        let repr = *sets.find(&5).unwrap();
+        assert!([4, 5].contains(&repr));
+        assert_eq!(*sets.find(&4).unwrap(), repr);
+        assert_ne!(*sets.find(&1).unwrap(), repr);
+    }
+
+    #[test]
+    fn test_iterate_sets() {
+        let mut sets = DisjointSets::new();
+        assert_eq!(sets.sets().count(), 0);
+        assert_eq!(sets.representatives().count(), 0);
+
+        for i in 1..=6 {
+            sets.make_set(i).unwrap();
+        }
+        // (1, 2, 3), (4, 5), (6)
+        sets.union(&1, &2).unwrap();
+        sets.union(&3, &2).unwrap();
+        sets.union(&5, &4).unwrap();
+
+        let mut groups: Vec<Vec<i32>> = sets
+            .sets()
+            .map(|set| {
+                let mut set: Vec<i32> = set.into_iter().copied().collect();
+                set.sort();
+                set
+            })
+            .collect();
+        groups.sort();
+        assert_eq!(groups, vec![vec![1, 2, 3], vec![4, 5], vec![6]]);
+
+        let mut reprs: Vec<i32> = sets.representatives().copied().collect();
+        reprs.sort();
+        let mut expected = vec![
+            *sets.find(&1).unwrap(),
+            *sets.find(&4).unwrap(),
+            *sets.find(&6).unwrap(),
+        ];
+        expected.sort();
+        assert_eq!(reprs, expected);
+
+        let mut members: Vec<i32> = sets.members(&2).unwrap().into_iter().copied().collect();
+        members.sort();
+        assert_eq!(members, vec![1, 2, 3]);
+        assert_eq!(sets.members(&6).unwrap(), vec![&6]);
+        assert!(matches!(sets.members(&7), Err(Error::ItemNotFound { .. })));
+    }
+
+    #[test]
+    fn test_parts_round_trip() {
+        let mut sets = DisjointSets::new();
+        for i in 1..=6 {
+            sets.make_set(i).unwrap();
+        }
+        sets.union(&1, &2).unwrap();
+        sets.union(&3, &4).unwrap();
+        sets.union(&1, &3).unwrap();
+        sets.union(&6, &5).unwrap();
+        sets.remove(&2).unwrap();
+
+        let parts = sets.to_parts();
+        assert_eq!(parts.items, vec![1, 3, 4, 5, 6]);
+        assert_eq!(parts.parents, vec![0, 0, 0, 4, 4]);
+
+        let mut restored = DisjointSets::from_parts(parts.clone()).unwrap();
+        assert_eq!(restored.to_parts(), parts);
+        assert_eq!(restored.num_sets(), sets.num_sets());
+        assert_eq!(restored.num_items(), sets.num_items());
+        for x in [1, 3, 4, 5, 6] {
+            assert_eq!(restored.set_size(&x).unwrap(), sets.set_size(&x).unwrap());
+            for y in [1, 3, 4, 5, 6] {
+                assert_eq!(
+                    restored.same_set(&x, &y).unwrap(),
+                    sets.same_set(&x, &y).unwrap()
+                );
+            }
+        }
+
+        // Parents need not point directly at the representative.
+        let chain = DisjointSetsParts {
+            items: vec!['a', 'b', 'c'],
+            parents: vec![1, 2, 2],
+        };
+        let mut restored = DisjointSets::from_parts(chain).unwrap();
+        assert_eq!(restored.set_size(&'a').unwrap(), 3);
+        assert_eq!(*restored.find(&'a').unwrap(), 'c');
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut sets = DisjointSets::new();
+        for i in 1..=5 {
+            sets.make_set(i).unwrap();
+        }
+        sets.union(&1, &2).unwrap();
+        sets.union(&4, &3).unwrap();
+        sets.union(&2, &3).unwrap();
+
+        let json = serde_json::to_string(&sets).unwrap();
+        assert_eq!(json, r#"{"items":[1,2,3,4,5],"parents":[0,0,0,0,4]}"#);
+        let mut restored: DisjointSets<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.to_parts(), sets.to_parts());
+        assert_eq!(restored.num_sets(), 2);
+        assert!(restored.same_set(&1, &4).unwrap());
+
+        let cycle = r#"{"items":[1,2],"parents":[1,0]}"#;
+        assert!(serde_json::from_str::<DisjointSets<i32>>(cycle).is_err());
+        let dangling = r#"{"items":[1,2],"parents":[0,2]}"#;
+        assert!(serde_json::from_str::<DisjointSets<i32>>(dangling).is_err());
+    }
+
+    #[test]
+    fn test_parts_validation() {
+        let parts = |items: Vec<i32>, parents: Vec<usize>| DisjointSetsParts { items, parents };
+
+        assert_eq!(
+            DisjointSets::from_parts(parts(vec![1, 2], vec![0])).unwrap_err(),
+            Error::Malformed
+        );
+        assert_eq!(
+            DisjointSets::from_parts(parts(vec![1, 2], vec![0, 2])).unwrap_err(),
+            Error::Malformed
+        );
+        assert_eq!(
+            DisjointSets::from_parts(parts(vec![1, 2, 3], vec![1, 2, 0])).unwrap_err(),
+            Error::Malformed
+        );
+        assert_eq!(
+            DisjointSets::from_parts(parts(vec![1, 2, 3, 4], vec![0, 2, 3, 1])).unwrap_err(),
+            Error::Malformed
+        );
+        assert!(matches!(
+            DisjointSets::from_parts(parts(vec![1, 1], vec![0, 0])),
+            Err(Error::ItemExists { .. })
+        ));
+        assert_eq!(
+            DisjointSets::<i32>::from_parts(parts(vec![], vec![]))
+                .unwrap()
+                .num_sets(),
+            0
+        );
+    }
+
+    /// Build disjoint sets of the items `0..100` from `num_edges` scattered
+    /// unions.
+    fn build<L: LinkPolicy, C: Compression>(
+        link_policy: L,
+        num_edges: u32,
+    ) -> DisjointSets<u32, L, C> {
+        let mut sets = DisjointSets::with_link_policy(link_policy);
+        for i in 0..100 {
+            sets.make_set(i).unwrap();
+        }
+        for i in 0..num_edges {
+            sets.union(&(i * 37 % 100), &(i * 61 % 97)).unwrap();
+        }
+        sets
+    }
+
+    /// Check that disjoint sets from `build` have the same sets.
+    fn assert_same_sets<L, C, M, D>(
+        x: &mut DisjointSets<u32, L, C>,
+        y: &mut DisjointSets<u32, M, D>,
+    ) where
+        L: LinkPolicy,
+        C: Compression,
+        M: LinkPolicy,
+        D: Compression,
+    {
+        assert_eq!(x.num_sets(), y.num_sets());
+        for a in 0..100 {
+            assert_eq!(x.set_size(&a).unwrap(), y.set_size(&a).unwrap());
+            for b in 0..100 {
+                assert_eq!(x.same_set(&a, &b).unwrap(), y.same_set(&a, &b).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_link_policies() {
+        let mut by_size = build::<_, FullCompression>(BySize, 60);
+        let mut by_rank = build::<_, FullCompression>(ByRank, 60);
+        let mut by_random_index = build::<_, FullCompression>(ByRandomIndex { seed: 7 }, 60);
+        assert_same_sets(&mut by_size, &mut by_rank);
+        assert_same_sets(&mut by_size, &mut by_random_index);
+    }
+
+    #[test]
+    fn test_union_by_rank() {
+        let mut sets = DisjointSets::with_link_policy(ByRank);
+        for i in 1..=5 {
+            sets.make_set(i).unwrap();
+        }
+        let rank = |sets: &mut DisjointSets<i32, ByRank>, item| {
+            let id = sets.id_of(item, Arg::Item).unwrap();
+            let repr = sets.find_repr_id(id);
+            sets.nodes.get(&repr).unwrap().rank()
+        };
+
+        // ((1, 2), 3) has rank 1 and size 3, (4, 5) has rank 1 and size 2.
+        sets.union(&1, &2).unwrap();
+        sets.union(&3, &1).unwrap();
+        sets.union(&4, &5).unwrap();
+        assert_eq!(rank(&mut sets, &3), 1);
+        assert_eq!(sets.set_size(&3).unwrap(), 3);
+        assert_eq!(rank(&mut sets, &5), 1);
+
+        // Equal ranks increase the rank of the merged tree.
+        sets.union(&4, &1).unwrap();
+        assert_eq!(rank(&mut sets, &1), 2);
+        assert_eq!(sets.set_size(&1).unwrap(), 5);
+        assert_eq!(*sets.find(&2).unwrap(), 4);
+    }
+
+    #[test]
+    fn test_compression_strategies() {
+        let mut full = build::<_, FullCompression>(BySize, 70);
+        let mut halving = build::<_, PathHalving>(BySize, 70);
+        let mut splitting = build::<_, PathSplitting>(BySize, 70);
+        let mut none = build::<_, NoCompression>(BySize, 70);
+        assert_same_sets(&mut full, &mut halving);
+        assert_same_sets(&mut full, &mut splitting);
+        assert_same_sets(&mut full, &mut none);
+    }
+
+    #[test]
+    fn test_deep_tree() {
+        // A chain this long overflows the stack with a recursive `find`.
+        const N: usize = 100_000;
+        let chain = || DisjointSetsParts {
+            items: (0..N).collect(),
+            parents: (1..N).chain([N - 1]).collect(),
+        };
+
+        let mut full = DisjointSets::from_parts(chain()).unwrap();
+        assert_eq!(*full.find(&0).unwrap(), N - 1);
+        assert_eq!(parent_of(&full.nodes, 0), (N - 1) as Id);
+        assert_eq!(parent_of(&full.nodes, N as Id / 2), (N - 1) as Id);
+
+        let mut halving: DisjointSets<usize, BySize, PathHalving> =
+            DisjointSets::from_parts_with_link_policy(chain(), BySize).unwrap();
+        assert_eq!(*halving.find(&0).unwrap(), N - 1);
+        assert_eq!(parent_of(&halving.nodes, 0), 2);
+
+        let mut splitting: DisjointSets<usize, BySize, PathSplitting> =
+            DisjointSets::from_parts_with_link_policy(chain(), BySize).unwrap();
+        assert_eq!(*splitting.find(&0).unwrap(), N - 1);
+        assert_eq!(parent_of(&splitting.nodes, 0), 2);
+        assert_eq!(parent_of(&splitting.nodes, 1), 3);
+
+        let mut none: DisjointSets<usize, BySize, NoCompression> =
+            DisjointSets::from_parts_with_link_policy(chain(), BySize).unwrap();
+        assert_eq!(*none.find(&0).unwrap(), N - 1);
+        assert_eq!(parent_of(&none.nodes, 0), 1);
+        assert_eq!(none.set_size(&0).unwrap(), N);
+    }
+
+    #[test]
+    fn test_set_stats() {
+        let mut sets = DisjointSets::new();
+        assert_eq!(sets.num_sets(), 0);
+        assert_eq!(sets.largest_set_size(), 0);
+        assert!(sets.set_size_histogram().is_empty());
+
+        for i in 1..=6 {
+            sets.make_set(i).unwrap();
+        }
+        assert_eq!(sets.largest_set_size(), 1);
+        assert_eq!(sets.set_size_histogram(), &BTreeMap::from([(1, 6)]));
+
+        // (1, 2, 3), (4, 5), (6)
+        sets.union(&1, &2).unwrap();
+        sets.union(&1, &3).unwrap();
+        sets.union(&4, &5).unwrap();
+        sets.union(&2, &3).unwrap();
+        assert_eq!(sets.num_sets(), 3);
+        assert_eq!(sets.largest_set_size(), 3);
+        assert_eq!(
+            sets.set_size_histogram(),
+            &BTreeMap::from([(1, 1), (2, 1), (3, 1)])
+        );
+
+        // (1, 2), (4, 5), (6)
+        sets.remove(&3).unwrap();
+        assert_eq!(sets.num_sets(), 3);
+        assert_eq!(sets.largest_set_size(), 2);
+        assert_eq!(sets.set_size_histogram(), &BTreeMap::from([(1, 1), (2, 2)]));
+
+        // (1, 2), (4, 5)
+        sets.remove(&6).unwrap();
+        assert_eq!(sets.num_sets(), 2);
+        assert_eq!(sets.set_size_histogram(), &BTreeMap::from([(2, 2)]));
+
+        let restored = DisjointSets::from_parts(sets.to_parts()).unwrap();
+        assert_eq!(restored.num_sets(), 2);
+        assert_eq!(restored.largest_set_size(), 2);
+        assert_eq!(restored.set_size_histogram(), sets.set_size_histogram());
+    }
+
+    #[test]
+    fn test_bulk_construction() {
+        let mut sets: DisjointSets<i32> = [1, 2, 3, 2, 1].into_iter().collect();
+        assert_eq!(sets.num_items(), 3);
+        assert_eq!(sets.num_sets(), 3);
+
+        sets.extend([3, 4, 5]);
+        assert_eq!(sets.num_items(), 5);
+
+        assert!(matches!(
+            sets.try_extend([6, 1, 7]),
+            Err(Error::ItemExists { .. })
+        ));
+        assert!(sets.contains(&6));
+        assert!(!sets.contains(&7));
+        sets.try_extend([7, 8]).unwrap();
+        assert_eq!(sets.num_items(), 8);
+
+        let mut sets = DisjointSets::from_edges([("a", "b"), ("c", "d"), ("b", "a"), ("e", "b")]);
+        assert_eq!(sets.num_items(), 5);
+        assert_eq!(sets.num_sets(), 2);
+        assert!(sets.same_set(&"a", &"e").unwrap());
+        assert!(!sets.same_set(&"a", &"c").unwrap());
+
+        let mut sets = DisjointSets::with_capacity(100);
+        assert!(sets.nodes.capacity() >= 100);
+        assert!(sets.item_to_id.capacity() >= 100);
+        assert!(sets.id_to_item.capacity() >= 100);
+        sets.make_set(1).unwrap();
+        sets.reserve(200);
+        assert!(sets.item_to_id.capacity() >= 201);
+    }
+
+    #[test]
+    fn test_insert_on_demand() {
+        let mut sets = DisjointSets::new();
+        assert_eq!(*sets.find_or_insert(1), 1);
+        assert_eq!(*sets.find_or_insert(1), 1);
+        assert_eq!(sets.num_items(), 1);
+
+        // (1, 2, 3), (4, 5)
+        sets.union_or_insert(1, 2);
+        sets.union_or_insert(3, 2);
+        sets.union_or_insert(4, 5);
+        sets.union_or_insert(1, 3);
+        assert_eq!(sets.num_items(), 5);
+        assert_eq!(sets.num_sets(), 2);
+        assert_eq!(sets.set_size(&3).unwrap(), 3);
+        assert!(sets.same_set(&2, &3).unwrap());
+        assert_eq!(sets.find_or_insert(3), &1);
+        assert_eq!(sets.find_or_insert(6), &6);
+        assert_eq!(sets.num_sets(), 3);
+    }
+
+    #[test]
+    fn test_with_items() {
+        let mut sets = DisjointSets::new();
+        sets.make_set_with_items("a").unwrap();
+        sets.make_set_with_items("b").unwrap();
+        sets.union_with_items(&"a", &"b").unwrap();
+        assert!(sets.same_set_with_items(&"b", &"a").unwrap());
+        assert_eq!(*sets.find_with_items(&"b").unwrap(), "a");
+
+        assert_eq!(
+            sets.make_set_with_items("b").unwrap_err().to_string(),
+            "item \"b\" is already in the disjoint sets"
+        );
+        assert_eq!(
+            sets.union_with_items(&"a", &"c").unwrap_err().to_string(),
+            "argument `y` (\"c\") is not in the disjoint sets"
+        );
+        assert_eq!(
+            sets.same_set_with_items(&"d", &"c")
+                .unwrap_err()
+                .to_string(),
+            "argument `x` (\"d\") is not in the disjoint sets"
+        );
+        assert_eq!(
+            sets.find_with_items(&"c").unwrap_err().to_string(),
+            "argument `item` (\"c\") is not in the disjoint sets"
+        );
+        assert_eq!(sets.num_items(), 2);
+    }
+
+    #[test]
+    fn test_entry() {
+        let mut sets = DisjointSets::new();
+        sets.make_set("a").unwrap();
+
+        match sets.entry("b") {
+            Entry::Vacant(entry) => assert_eq!(entry.into_item(), "b"),
+            Entry::Occupied(_) => panic!("`b` should not exist"),
+        }
+        assert!(!sets.contains(&"b"));
+
+        let mut entry = sets.entry("b").or_insert();
+        assert_eq!(*entry.item(), "b");
+        assert_eq!(entry.set_size(), 1);
+        assert_eq!(*entry.find(), "b");
+        sets.union(&"b", &"a").unwrap();
+
+        match sets.entry("a") {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(*entry.item(), "a");
+                assert_eq!(*entry.find(), "b");
+                assert_eq!(entry.set_size(), 2);
+            }
+            Entry::Vacant(_) => panic!("`a` should exist"),
+        }
+        assert_eq!(*sets.entry("c").item(), "c");
+        assert_eq!(sets.num_items(), 2);
+        assert_eq!(sets.num_sets(), 1);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut sets = DisjointSets::new();
+        for i in 1..=6 {
+            sets.make_set(i).unwrap();
+        }
+        assert!(matches!(sets.remove(&7), Err(Error::ItemNotFound { .. })));
+
+        // Build the tree 4 -> 3 -> 1 <- 2, plus (5), (6).
+        sets.union(&1, &2).unwrap();
+        sets.union(&3, &4).unwrap();
+        sets.union(&1, &3).unwrap();
+
+        // Remove a singleton.
+        assert_eq!(sets.remove(&6).unwrap(), 6);
+        assert!(!sets.contains(&6));
+        assert_eq!(sets.num_items(), 5);
+        assert_eq!(sets.num_sets(), 2);
+
+        // Remove an interior node.
+        assert_eq!(sets.remove(&3).unwrap(), 3);
+        assert_eq!(sets.num_items(), 4);
+        assert_eq!(sets.num_sets(), 2);
+        assert_eq!(sets.set_size(&4).unwrap(), 3);
+        assert!(sets.same_set(&4, &2).unwrap());
+        assert!(matches!(
+            sets.same_set(&3, &1),
+            Err(Error::ItemNotFound { .. })
+        ));
+
+        // Remove the representative.
+        assert_eq!(*sets.find_immutable(&2).unwrap(), 1);
+        assert_eq!(sets.remove(&1).unwrap(), 1);
+        assert_eq!(sets.num_items(), 3);
+        assert_eq!(sets.num_sets(), 2);
+        assert_eq!(sets.set_size(&2).unwrap(), 2);
+        assert!(sets.same_set(&2, &4).unwrap());
+        assert!(!sets.same_set(&2, &5).unwrap());
+        assert!([2, 4].contains(sets.find(&4).unwrap()));
+
+        // The removed item can be added again as a singleton.
+        sets.make_set(1).unwrap();
+        assert!(!sets.same_set(&1, &2).unwrap());
+        assert_eq!(sets.num_sets(), 3);
+    }
+
+    #[test]
+    fn test_remove_many() {
+        let mut sets: DisjointSets<i32> = (0..100).collect();
+        let mut labels: HashMap<i32, i32> = (0..100).map(|i| (i, i)).collect();
+        for i in (0..100).filter(|i| i % 3 != 0) {
+            let (x, y) = (i, i * 37 % 100);
+            sets.union(&x, &y).unwrap();
+            let (x_label, y_label) = (labels[&x], labels[&y]);
+            for label in labels.values_mut().filter(|l| **l == y_label) {
+                *label = x_label;
+            }
+        }
+
+        // 61 is coprime to 100, so every item is removed once.
+        for i in 0..100 {
+            let x = i * 61 % 100;
+            assert_eq!(sets.remove(&x).unwrap(), x);
+            labels.remove(&x);
+            assert!(sets.nodes.len() <= 2 * sets.num_items());
+
+            for (y, label) in &labels {
+                let mut expected: Vec<i32> = labels
+                    .iter()
+                    .filter(|(_, l)| *l == label)
+                    .map(|(z, _)| *z)
+                    .collect();
+                expected.sort_unstable();
+                let mut members: Vec<i32> = sets.members(y).unwrap().into_iter().copied().collect();
+                members.sort_unstable();
+                assert_eq!(members, expected);
+                assert_eq!(sets.set_size(y).unwrap(), expected.len());
+                assert_eq!(labels[sets.find(y).unwrap()], *label);
+            }
+            let mut distinct: Vec<i32> = labels.values().copied().collect();
+            distinct.sort_unstable();
+            distinct.dedup();
+            assert_eq!(sets.num_sets(), distinct.len());
+            assert_eq!(sets.representatives().count(), distinct.len());
+        }
+        assert!(sets.nodes.is_empty());
+    }
+
+    #[derive(Debug, Default)]
+    struct Recorder(Vec<String>);
+
+    impl UnionObserver<i32> for Recorder {
+        fn on_make_set(&mut self, item: &i32) {
+            self.0.push(format!("make_set {}", item));
+        }
+
+        fn on_union(&mut self, winner: &i32, loser: &i32, new_size: usize) {
+            self.0
+                .push(format!("union {} {} {}", winner, loser, new_size));
+        }
+
+        fn on_remove(&mut self, item: &i32, new_repr: Option<&i32>) {
+            match new_repr {
+                Some(new_repr) => self.0.push(format!("remove {} {}", item, new_repr)),
+                None => self.0.push(format!("remove {}", item)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_observer() {
+        let mut sets = DisjointSets::new();
+        sets.make_set(1).unwrap();
+        let mut sets = sets.with_observer(Recorder::default());
+
+        sets.make_set(2).unwrap();
+        assert!(sets.make_set(2).is_err());
+        sets.union(&1, &2).unwrap();
+        sets.union(&2, &1).unwrap();
+        sets.union_or_insert(3, 4);
+        // With equal sizes, the set of the first argument wins.
+        sets.union(&3, &2).unwrap();
+        sets.remove(&4).unwrap();
+        assert!(sets.remove(&4).is_err());
+        assert_eq!(
+            sets.observer().0,
+            [
+                "make_set 2",
+                "union 1 2 2",
+                "make_set 3",
+                "make_set 4",
+                "union 3 4 2",
+                "union 3 1 4",
+                "remove 4"
+            ]
+        );
+
+        sets.observer_mut().0.clear();
+        sets.union(&1, &3).unwrap();
+        assert!(sets.observer().0.is_empty());
+
+        let mut sets: DisjointSets<i32, BySize, FullCompression, Recorder> = (5..7).collect();
+        sets.entry(7).or_insert();
+        sets.entry(5).or_insert();
+        assert_eq!(
+            sets.observer().0,
+            ["make_set 5", "make_set 6", "make_set 7"]
+        );
+    }
+
+    #[test]
+    fn test_observer_remove_representative() {
+        let mut sets = DisjointSets::new().with_observer(Recorder::default());
+        for i in 1..=3 {
+            sets.make_set(i).unwrap();
+        }
+        sets.union(&1, &2).unwrap();
+        sets.union(&1, &3).unwrap();
+        sets.observer_mut().0.clear();
+
+        // The tree is 2 -> 1 <- 3, so a child of 1 takes its place.
+        sets.remove(&1).unwrap();
+        let new_repr = *sets.find(&2).unwrap();
+        assert!(new_repr == 2 || new_repr == 3);
+        assert_eq!(sets.find(&3).unwrap(), &new_repr);
+
+        // Removing a representative that is alone in its set reports none.
+        sets.make_set(4).unwrap();
+        sets.remove(&4).unwrap();
+        assert_eq!(
+            sets.observer().0,
+            [
+                format!("remove 1 {}", new_repr),
+                "make_set 4".to_string(),
+                "remove 4".to_string()
+            ]
+        );
+    }
 }
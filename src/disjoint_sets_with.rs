@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::disjoint_sets::{DisjointSets, Id};
+use crate::union_find::{Arg, Result, UnionFind};
+
+/// Combines the values of two sets when they are merged. The merge should be
+/// associative, since sets can be merged in any order.
+pub trait Merge<V> {
+    fn merge(&self, x: V, y: V) -> V;
+}
+
+impl<V, F> Merge<V> for F
+where
+    F: Fn(V, V) -> V,
+{
+    fn merge(&self, x: V, y: V) -> V {
+        self(x, y)
+    }
+}
+
+/// Disjoint sets where every set carries a value, and the values of two sets
+/// are combined with `M` when they are merged.
+#[derive(Clone, Debug)]
+pub struct DisjointSetsWith<T, V, M> {
+    sets: DisjointSets<T>,
+    /// Only representatives have a value.
+    values: HashMap<Id, V>,
+    merge: M,
+}
+
+impl<T, V, M> DisjointSetsWith<T, V, M>
+where
+    T: Eq + Hash + Clone,
+    M: Merge<V>,
+{
+    pub fn new(merge: M) -> Self {
+        DisjointSetsWith {
+            sets: DisjointSets::new(),
+            values: HashMap::new(),
+            merge,
+        }
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.sets.contains(item)
+    }
+
+    pub fn set_size(&mut self, item: &T) -> Result<usize> {
+        self.sets.set_size(item)
+    }
+
+    pub fn num_sets(&self) -> usize {
+        self.sets.num_sets()
+    }
+
+    pub fn num_items(&self) -> usize {
+        self.sets.num_items()
+    }
+
+    /// Create a new set containing only `item`, with `value` as its value. If
+    /// `item` already exists in the disjoint sets, an error is returned.
+    pub fn make_set(&mut self, item: T, value: V) -> Result<()> {
+        self.sets.make_set(item.clone())?;
+        let id = self.sets.id_of(&item, Arg::Item).unwrap();
+        self.values.insert(id, value);
+        Ok(())
+    }
+
+    /// Find the representative of the set containing `item`. If `item` does
+    /// not exist in the disjoint sets, an error is returned.
+    pub fn find(&mut self, item: &T) -> Result<&T> {
+        self.sets.find(item)
+    }
+
+    /// Check if two items are in the same set. If `x` or `y` do not exist in
+    /// the disjoint sets, an error is returned.
+    pub fn same_set(&mut self, x: &T, y: &T) -> Result<bool> {
+        self.sets.same_set(x, y)
+    }
+
+    /// Merge the sets containing `x` and `y`, combining the value of the set
+    /// of `x` with the value of the set of `y`. If `x` or `y` do not exist in
+    /// the disjoint sets, an error is returned.
+    pub fn union(&mut self, x: &T, y: &T) -> Result<()> {
+        let x_repr = self.find_repr_id(self.sets.id_of(x, Arg::X)?);
+        let y_repr = self.find_repr_id(self.sets.id_of(y, Arg::Y)?);
+
+        if x_repr != y_repr {
+            let x_value = self.values.remove(&x_repr).unwrap();
+            let y_value = self.values.remove(&y_repr).unwrap();
+            let repr = self.sets.link(x_repr, y_repr);
+            self.values.insert(repr, self.merge.merge(x_value, y_value));
+        }
+
+        Ok(())
+    }
+
+    /// Get the value of the set containing `item`. If `item` does not exist
+    /// in the disjoint sets, an error is returned.
+    pub fn value(&self, item: &T) -> Result<&V> {
+        let repr = self.find_repr_id(self.sets.id_of(item, Arg::Item)?);
+        Ok(&self.values[&repr])
+    }
+
+    /// Get a mutable reference to the value of the set containing `item`. If
+    /// `item` does not exist in the disjoint sets, an error is returned.
+    pub fn value_mut(&mut self, item: &T) -> Result<&mut V> {
+        let repr = self.find_repr_id(self.sets.id_of(item, Arg::Item)?);
+        Ok(self.values.get_mut(&repr).unwrap())
+    }
+
+    /// Assumes `id` exists.
+    fn find_repr_id(&self, id: Id) -> Id {
+        self.sets.find_repr_id_with(id, &mut |_, _| {})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::union_find::Error;
+
+    #[test]
+    fn test_merge_values() {
+        let mut sets = DisjointSetsWith::new(|x: u32, y: u32| x + y);
+        for i in 1..=5 {
+            sets.make_set(i, i * 10).unwrap();
+        }
+        assert!(matches!(sets.make_set(1, 0), Err(Error::ItemExists { .. })));
+        assert!(matches!(
+            sets.value(&6),
+            Err(Error::ItemNotFound { arg: Arg::Item, .. })
+        ));
+
+        // (1, 2, 3), (4), (5)
+        sets.union(&1, &2).unwrap();
+        sets.union(&3, &2).unwrap();
+        sets.union(&1, &3).unwrap();
+        assert_eq!(*sets.value(&1).unwrap(), 60);
+        assert_eq!(*sets.value(&3).unwrap(), 60);
+        assert_eq!(*sets.value(&4).unwrap(), 40);
+        assert_eq!(sets.set_size(&2).unwrap(), 3);
+
+        *sets.value_mut(&5).unwrap() += 1;
+        sets.union(&4, &5).unwrap();
+        assert_eq!(*sets.value(&5).unwrap(), 91);
+        assert_eq!(sets.num_sets(), 2);
+    }
+
+    #[test]
+    fn test_merge_order() {
+        struct Concat;
+
+        impl Merge<String> for Concat {
+            fn merge(&self, x: String, y: String) -> String {
+                x + &y
+            }
+        }
+
+        let mut sets = DisjointSetsWith::new(Concat);
+        for item in ["a", "b", "c"] {
+            sets.make_set(item, item.to_string()).unwrap();
+        }
+        sets.union(&"b", &"a").unwrap();
+        sets.union(&"c", &"a").unwrap();
+        assert_eq!(sets.value(&"a").unwrap(), "cba");
+    }
+}
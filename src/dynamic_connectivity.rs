@@ -0,0 +1,400 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::euler_tour_forest::EulerTourForest;
+use crate::item_table::ItemTable;
+use crate::union_find::{Arg, Result, UnionFind};
+
+type Id = usize;
+
+/// Flag of vertices with tree edges of the level of the forest.
+const TREE_EDGES: u8 = 1;
+/// Flag of vertices with non-tree edges of the level of the forest.
+const NON_TREE_EDGES: u8 = 2;
+
+/// A graph that supports inserting and deleting edges and connectivity queries
+/// in any order, with the algorithm of Holm, de Lichtenberg and Thorup.
+///
+/// Every edge has a level, which only increases, and the spanning forest
+/// `F_i` of the edges of level at least `i` is kept for every level, so that
+/// `F_0` is a spanning forest of the whole graph. Deleting an edge of `F_0`
+/// searches for a replacement edge among the non-tree edges, from the highest
+/// level down. Edges that are searched in vain move up a level, and since a
+/// tree of `F_i` has at most `n / 2^i` vertices, there are at most `log2 n`
+/// levels. Updates take `O(log^2 n)` amortized expected time, and connectivity
+/// queries `O(log n)` expected time.
+///
+/// The `UnionFind` implementation inserts an edge on `union`, and `find`
+/// returns a representative that changes whenever the component of the item
+/// is updated.
+#[derive(Clone, Debug)]
+pub struct DynamicConnectivity<T> {
+    items: ItemTable<T>,
+    forest: EulerTourForest,
+    /// The node of every vertex in the forest of every level.
+    levels: Vec<Vec<usize>>,
+    /// The neighbours of every vertex at every level.
+    adjacency: Vec<Vec<Adjacency>>,
+    /// The edges by their endpoints, with the smaller ID first.
+    edges: HashMap<(Id, Id), Edge>,
+    num_sets: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Adjacency {
+    tree: HashSet<Id>,
+    non_tree: HashSet<Id>,
+}
+
+#[derive(Clone, Debug)]
+struct Edge {
+    level: usize,
+    /// The number of copies of the edge in the graph.
+    count: usize,
+    /// For a tree edge, the arc nodes of the edge in the forests of levels
+    /// `0..=level`.
+    arcs: Vec<[usize; 2]>,
+}
+
+impl<T> Default for DynamicConnectivity<T>
+where
+    T: Eq + Hash + Clone,
+{
+    fn default() -> Self {
+        DynamicConnectivity::new()
+    }
+}
+
+impl<T> DynamicConnectivity<T>
+where
+    T: Eq + Hash + Clone,
+{
+    pub fn new() -> Self {
+        DynamicConnectivity {
+            items: ItemTable::new(),
+            forest: EulerTourForest::new(),
+            levels: vec![Vec::new()],
+            adjacency: vec![Vec::new()],
+            edges: HashMap::new(),
+            num_sets: 0,
+        }
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.items.contains(item)
+    }
+
+    /// Get the number of connected components.
+    pub fn num_sets(&self) -> usize {
+        self.num_sets
+    }
+
+    pub fn num_items(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Check if `x` and `y` are connected. If `x` or `y` do not exist in the
+    /// graph, an error is returned.
+    pub fn connected(&self, x: &T, y: &T) -> Result<bool> {
+        let x = self.items.index_of(x, Arg::X)?;
+        let y = self.items.index_of(y, Arg::Y)?;
+        Ok(self.forest.connected(self.levels[0][x], self.levels[0][y]))
+    }
+
+    /// Insert an edge between `x` and `y`. An edge can be inserted several
+    /// times, and is only gone once every copy is deleted. If `x` or `y` do
+    /// not exist in the graph, an error is returned.
+    pub fn insert_edge(&mut self, x: &T, y: &T) -> Result<()> {
+        let x = self.items.index_of(x, Arg::X)?;
+        let y = self.items.index_of(y, Arg::Y)?;
+        let key = (x.min(y), x.max(y));
+        if let Some(edge) = self.edges.get_mut(&key) {
+            edge.count += 1;
+            return Ok(());
+        }
+
+        let mut edge = Edge {
+            level: 0,
+            count: 1,
+            arcs: Vec::new(),
+        };
+        // Loops never connect anything, so they are only counted.
+        if x != y {
+            if self.forest.connected(self.levels[0][x], self.levels[0][y]) {
+                self.add_non_tree_edge(0, x, y);
+            } else {
+                edge.arcs.push(self.link(0, x, y));
+                self.add_tree_edge(0, x, y);
+                self.num_sets -= 1;
+            }
+        }
+        self.edges.insert(key, edge);
+        Ok(())
+    }
+
+    /// Delete one copy of the edge between `x` and `y`. Returns whether the
+    /// edge existed. If `x` or `y` do not exist in the graph, an error is
+    /// returned.
+    pub fn delete_edge(&mut self, x: &T, y: &T) -> Result<bool> {
+        let x = self.items.index_of(x, Arg::X)?;
+        let y = self.items.index_of(y, Arg::Y)?;
+        let key = (x.min(y), x.max(y));
+        let Some(edge) = self.edges.get_mut(&key) else {
+            return Ok(false);
+        };
+        edge.count -= 1;
+        if edge.count > 0 {
+            return Ok(true);
+        }
+
+        let Edge { level, arcs, .. } = self.edges.remove(&key).unwrap();
+        if x == y {
+            return Ok(true);
+        }
+        if arcs.is_empty() {
+            self.remove_non_tree_edge(level, x, y);
+            return Ok(true);
+        }
+
+        for arcs in arcs {
+            self.forest.cut(arcs);
+        }
+        self.adjacency[level][x].tree.remove(&y);
+        self.adjacency[level][y].tree.remove(&x);
+        self.update_flags(level, x);
+        self.update_flags(level, y);
+
+        match (0..=level)
+            .rev()
+            .find_map(|i| self.find_replacement(i, x, y))
+        {
+            Some((level, u, v)) => {
+                self.remove_non_tree_edge(level, u, v);
+                let arcs = (0..=level).map(|i| self.link(i, u, v)).collect();
+                self.add_tree_edge(level, u, v);
+                self.edges.get_mut(&(u.min(v), u.max(v))).unwrap().arcs = arcs;
+            }
+            None => self.num_sets += 1,
+        }
+        Ok(true)
+    }
+
+    /// Find a non-tree edge of `level` that reconnects the trees of `x` and
+    /// `y` in the forest of `level`, after moving the edges of the smaller
+    /// tree up a level.
+    fn find_replacement(&mut self, level: usize, x: Id, y: Id) -> Option<(usize, Id, Id)> {
+        let (x_node, y_node) = (self.levels[level][x], self.levels[level][y]);
+        let small = if self.forest.num_vertices(x_node) <= self.forest.num_vertices(y_node) {
+            x_node
+        } else {
+            y_node
+        };
+
+        // The smaller tree fits into a forest of the next level.
+        while let Some(u) = self.forest.find_flagged(small, TREE_EDGES) {
+            for v in std::mem::take(&mut self.adjacency[level][u].tree) {
+                self.adjacency[level][v].tree.remove(&u);
+                self.update_flags(level, v);
+                let arcs = self.link(level + 1, u, v);
+                self.add_tree_edge(level + 1, u, v);
+                let edge = self.edges.get_mut(&(u.min(v), u.max(v))).unwrap();
+                edge.level += 1;
+                edge.arcs.push(arcs);
+            }
+            self.update_flags(level, u);
+        }
+
+        while let Some(u) = self.forest.find_flagged(small, NON_TREE_EDGES) {
+            let neighbours: Vec<Id> = self.adjacency[level][u].non_tree.iter().copied().collect();
+            for v in neighbours {
+                if !self.forest.connected(small, self.levels[level][v]) {
+                    return Some((level, u, v));
+                }
+
+                self.remove_non_tree_edge(level, u, v);
+                self.add_non_tree_edge(level + 1, u, v);
+                self.edges.get_mut(&(u.min(v), u.max(v))).unwrap().level += 1;
+            }
+        }
+        None
+    }
+
+    /// Link the trees of `x` and `y` in the forest of `level`, and return the
+    /// arc nodes of the new edge.
+    fn link(&mut self, level: usize, x: Id, y: Id) -> [usize; 2] {
+        self.ensure_level(level);
+        self.forest
+            .link(self.levels[level][x], self.levels[level][y])
+    }
+
+    /// Record a tree edge of exactly `level`.
+    fn add_tree_edge(&mut self, level: usize, x: Id, y: Id) {
+        self.adjacency[level][x].tree.insert(y);
+        self.adjacency[level][y].tree.insert(x);
+        self.update_flags(level, x);
+        self.update_flags(level, y);
+    }
+
+    fn add_non_tree_edge(&mut self, level: usize, x: Id, y: Id) {
+        self.ensure_level(level);
+        self.adjacency[level][x].non_tree.insert(y);
+        self.adjacency[level][y].non_tree.insert(x);
+        self.update_flags(level, x);
+        self.update_flags(level, y);
+    }
+
+    fn remove_non_tree_edge(&mut self, level: usize, x: Id, y: Id) {
+        self.adjacency[level][x].non_tree.remove(&y);
+        self.adjacency[level][y].non_tree.remove(&x);
+        self.update_flags(level, x);
+        self.update_flags(level, y);
+    }
+
+    fn update_flags(&mut self, level: usize, id: Id) {
+        let adjacency = &self.adjacency[level][id];
+        let mut flags = 0;
+        if !adjacency.tree.is_empty() {
+            flags |= TREE_EDGES;
+        }
+        if !adjacency.non_tree.is_empty() {
+            flags |= NON_TREE_EDGES;
+        }
+        self.forest.set_flags(self.levels[level][id], flags);
+    }
+
+    /// Add the forests of all levels up to `level`, in which every vertex is
+    /// a tree of its own.
+    fn ensure_level(&mut self, level: usize) {
+        while self.levels.len() <= level {
+            let nodes = (0..self.items.len())
+                .map(|id| self.forest.add_vertex(id))
+                .collect();
+            self.levels.push(nodes);
+            self.adjacency
+                .push(vec![Adjacency::default(); self.items.len()]);
+        }
+    }
+}
+
+impl<T> UnionFind<T> for DynamicConnectivity<T>
+where
+    T: Eq + Hash + Clone,
+{
+    fn same_set(&mut self, x: &T, y: &T) -> Result<bool> {
+        self.connected(x, y)
+    }
+
+    fn find(&mut self, item: &T) -> Result<&T> {
+        let id = self.items.index_of(item, Arg::Item)?;
+        let repr = self.forest.first_vertex(self.levels[0][id]);
+        Ok(&self.items[repr])
+    }
+
+    fn make_set(&mut self, item: T) -> Result<()> {
+        let id = self.items.insert(item)?;
+        for level in 0..self.levels.len() {
+            let node = self.forest.add_vertex(id);
+            self.levels[level].push(node);
+            self.adjacency[level].push(Adjacency::default());
+        }
+        self.num_sets += 1;
+        Ok(())
+    }
+
+    /// Insert an edge between `x` and `y`.
+    fn union(&mut self, x: &T, y: &T) -> Result<()> {
+        self.insert_edge(x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disjoint_sets::DisjointSets;
+    use crate::test_rng::TestRng;
+    use crate::union_find::Error;
+
+    #[test]
+    fn test_dynamic_connectivity() {
+        let mut graph = DynamicConnectivity::new();
+        for i in 1..=5 {
+            graph.make_set(i).unwrap();
+        }
+        assert!(matches!(graph.make_set(1), Err(Error::ItemExists { .. })));
+        assert!(matches!(
+            graph.insert_edge(&1, &6),
+            Err(Error::ItemNotFound { arg: Arg::Y, .. })
+        ));
+
+        // A cycle 1 - 2 - 3 - 1, with the edge 2 - 3 twice, and 4 - 5.
+        graph.insert_edge(&1, &2).unwrap();
+        graph.insert_edge(&2, &3).unwrap();
+        graph.insert_edge(&3, &1).unwrap();
+        graph.union(&3, &2).unwrap();
+        graph.insert_edge(&4, &5).unwrap();
+        graph.insert_edge(&4, &4).unwrap();
+        assert_eq!(graph.num_sets(), 2);
+        assert!(graph.connected(&1, &3).unwrap());
+        assert!(!graph.same_set(&3, &4).unwrap());
+        let repr = *graph.find(&1).unwrap();
+        assert_eq!(*graph.find(&2).unwrap(), repr);
+
+        // The cycle stays connected until two distinct edges are gone.
+        assert!(graph.delete_edge(&1, &2).unwrap());
+        assert!(graph.connected(&1, &2).unwrap());
+        assert!(graph.delete_edge(&3, &2).unwrap());
+        assert!(graph.connected(&1, &2).unwrap());
+        assert!(graph.delete_edge(&2, &3).unwrap());
+        assert!(!graph.delete_edge(&2, &3).unwrap());
+        assert!(!graph.connected(&1, &2).unwrap());
+        assert!(graph.connected(&1, &3).unwrap());
+        assert_eq!(graph.num_sets(), 3);
+
+        assert!(graph.delete_edge(&4, &4).unwrap());
+        assert!(graph.connected(&4, &5).unwrap());
+        assert!(graph.delete_edge(&5, &4).unwrap());
+        assert!(!graph.connected(&4, &5).unwrap());
+        assert_eq!(graph.num_sets(), 4);
+    }
+
+    #[test]
+    fn test_random_updates() {
+        let mut rng = TestRng::new();
+        let mut next = |n: u64| rng.below(n);
+
+        for num_items in [2, 10, 50] {
+            let mut graph = DynamicConnectivity::new();
+            for i in 0..num_items {
+                graph.make_set(i).unwrap();
+            }
+
+            let mut edges: Vec<(u64, u64)> = Vec::new();
+            for step in 0..3000 {
+                // Alternate between phases of growth and phases of decay, so
+                // that components both form and break up.
+                let insert_weight = if step / 250 % 2 == 0 { 3 } else { 1 };
+                if edges.is_empty() || next(4) < insert_weight {
+                    let (x, y) = (next(num_items), next(num_items));
+                    graph.insert_edge(&x, &y).unwrap();
+                    edges.push((x, y));
+                } else {
+                    let (x, y) = edges.swap_remove(next(edges.len() as u64) as usize);
+                    assert!(graph.delete_edge(&y, &x).unwrap());
+                }
+
+                let mut sets: DisjointSets<u64> = (0..num_items).collect();
+                for (x, y) in &edges {
+                    sets.union(x, y).unwrap();
+                }
+                assert_eq!(graph.num_sets(), sets.num_sets());
+                for _ in 0..5 {
+                    let (x, y) = (next(num_items), next(num_items));
+                    assert_eq!(
+                        graph.connected(&x, &y).unwrap(),
+                        sets.same_set(&x, &y).unwrap()
+                    );
+                }
+            }
+        }
+    }
+}
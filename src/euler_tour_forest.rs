@@ -0,0 +1,331 @@
+const NIL: usize = usize::MAX;
+
+/// A forest stored as the Euler tours of its trees, which supports linking
+/// and cutting trees and connectivity queries in `O(log n)` expected time.
+///
+/// Every tour is a sequence with one node per vertex and two arc nodes per
+/// edge, kept in a treap with implicit keys. The sequence of a tree can be
+/// rotated to start at any of its vertices, which makes that vertex the root
+/// of the tour. Vertex nodes carry flags, and every treap node knows which
+/// flags occur in its subtree, so a flagged vertex of a tree can be found by
+/// descending from the treap root.
+#[derive(Clone, Debug)]
+pub struct EulerTourForest {
+    nodes: Vec<Node>,
+    free: Vec<usize>,
+    /// xorshift64 state for the treap priorities.
+    state: u64,
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    left: usize,
+    right: usize,
+    parent: usize,
+    priority: u64,
+    /// The vertex of a vertex node, or `NIL` for an arc node.
+    vertex: usize,
+    flags: u8,
+    /// The number of nodes in the subtree.
+    size: usize,
+    /// The number of vertex nodes in the subtree.
+    num_vertices: usize,
+    subtree_flags: u8,
+}
+
+impl EulerTourForest {
+    pub fn new() -> Self {
+        EulerTourForest {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            state: 0x9e37_79b9_7f4a_7c15,
+        }
+    }
+
+    /// Add `vertex` as a tree of its own and return its node.
+    pub fn add_vertex(&mut self, vertex: usize) -> usize {
+        self.alloc(vertex)
+    }
+
+    /// Check if the nodes `x` and `y` are in the same tree.
+    pub fn connected(&self, x: usize, y: usize) -> bool {
+        self.root(x) == self.root(y)
+    }
+
+    /// Get the number of vertices in the tree containing the node `x`.
+    pub fn num_vertices(&self, x: usize) -> usize {
+        self.nodes[self.root(x)].num_vertices
+    }
+
+    /// Get the first vertex in the tour of the tree containing the node `x`.
+    /// It only changes when the tree is linked or cut.
+    pub fn first_vertex(&self, x: usize) -> usize {
+        let mut x = self.root(x);
+        loop {
+            let left = self.nodes[x].left;
+            if self.num_vertices_of(left) > 0 {
+                x = left;
+            } else if self.nodes[x].vertex != NIL {
+                return self.nodes[x].vertex;
+            } else {
+                x = self.nodes[x].right;
+            }
+        }
+    }
+
+    /// Replace the flags of the vertex node `x`.
+    pub fn set_flags(&mut self, mut x: usize, flags: u8) {
+        self.nodes[x].flags = flags;
+        while x != NIL {
+            self.update(x);
+            x = self.nodes[x].parent;
+        }
+    }
+
+    /// Find a vertex with any of `flags` in the tree containing the node `x`.
+    pub fn find_flagged(&self, x: usize, flags: u8) -> Option<usize> {
+        let mut x = self.root(x);
+        if self.nodes[x].subtree_flags & flags == 0 {
+            return None;
+        }
+        loop {
+            let node = &self.nodes[x];
+            if node.flags & flags != 0 {
+                return Some(node.vertex);
+            }
+            x = if self.subtree_flags_of(node.left) & flags != 0 {
+                node.left
+            } else {
+                node.right
+            };
+        }
+    }
+
+    /// Link the trees of the distinct vertex nodes `x` and `y` with an edge,
+    /// and return the arc nodes of the edge, which are needed to cut it.
+    pub fn link(&mut self, x: usize, y: usize) -> [usize; 2] {
+        let x_tour = self.reroot(x);
+        let y_tour = self.reroot(y);
+        let arcs = [self.alloc(NIL), self.alloc(NIL)];
+        let tour = self.merge(x_tour, arcs[0]);
+        let tour = self.merge(tour, y_tour);
+        self.merge(tour, arcs[1]);
+        arcs
+    }
+
+    /// Cut the edge with the arc nodes `arcs`, as returned by `link`.
+    pub fn cut(&mut self, arcs: [usize; 2]) {
+        let root = self.root(arcs[0]);
+        let (i, j) = (self.index(arcs[0]), self.index(arcs[1]));
+        let (first, second) = (i.min(j), i.max(j));
+
+        // The tour is `a, arc, inner, arc, c`, where `inner` is the tour of
+        // one side of the edge and `c, a` the tour of the other.
+        let (a, rest) = self.split(root, first);
+        let (middle, c) = self.split(rest, second - first + 1);
+        let (_, middle) = self.split(middle, 1);
+        self.split(middle, self.nodes[middle].size - 1);
+        self.merge(c, a);
+
+        self.free.extend(arcs);
+    }
+
+    fn alloc(&mut self, vertex: usize) -> usize {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        let node = Node {
+            left: NIL,
+            right: NIL,
+            parent: NIL,
+            priority: self.state,
+            vertex,
+            flags: 0,
+            size: 1,
+            num_vertices: usize::from(vertex != NIL),
+            subtree_flags: 0,
+        };
+
+        match self.free.pop() {
+            Some(x) => {
+                self.nodes[x] = node;
+                x
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    fn root(&self, mut x: usize) -> usize {
+        while self.nodes[x].parent != NIL {
+            x = self.nodes[x].parent;
+        }
+        x
+    }
+
+    /// Get the position of `x` in its tour.
+    fn index(&self, mut x: usize) -> usize {
+        let mut index = self.size_of(self.nodes[x].left);
+        while self.nodes[x].parent != NIL {
+            let parent = self.nodes[x].parent;
+            if self.nodes[parent].right == x {
+                index += self.size_of(self.nodes[parent].left) + 1;
+            }
+            x = parent;
+        }
+        index
+    }
+
+    /// Rotate the tour containing the vertex node `x` to start at `x`, and
+    /// return the new treap root.
+    fn reroot(&mut self, x: usize) -> usize {
+        let root = self.root(x);
+        let (before, after) = self.split(root, self.index(x));
+        self.merge(after, before)
+    }
+
+    /// Concatenate the tours with the treap roots `x` and `y`.
+    fn merge(&mut self, x: usize, y: usize) -> usize {
+        if x == NIL {
+            return y;
+        }
+        if y == NIL {
+            return x;
+        }
+
+        if self.nodes[x].priority > self.nodes[y].priority {
+            let right = self.merge(self.nodes[x].right, y);
+            self.nodes[x].right = right;
+            self.nodes[right].parent = x;
+            self.update(x);
+            x
+        } else {
+            let left = self.merge(x, self.nodes[y].left);
+            self.nodes[y].left = left;
+            self.nodes[left].parent = y;
+            self.update(y);
+            y
+        }
+    }
+
+    /// Split the tour with the treap root `x` into its first `k` nodes and
+    /// the rest, and return the treap roots of both.
+    fn split(&mut self, x: usize, k: usize) -> (usize, usize) {
+        if x == NIL {
+            return (NIL, NIL);
+        }
+
+        let left = self.nodes[x].left;
+        let (first, second) = if self.size_of(left) >= k {
+            let (first, second) = self.split(left, k);
+            self.nodes[x].left = second;
+            self.set_parent(second, x);
+            (first, x)
+        } else {
+            let right = self.nodes[x].right;
+            let (first, second) = self.split(right, k - self.size_of(left) - 1);
+            self.nodes[x].right = first;
+            self.set_parent(first, x);
+            (x, second)
+        };
+        self.update(x);
+        self.set_parent(first, NIL);
+        self.set_parent(second, NIL);
+        (first, second)
+    }
+
+    fn update(&mut self, x: usize) {
+        let Node { left, right, .. } = self.nodes[x];
+        let size = 1 + self.size_of(left) + self.size_of(right);
+        let num_vertices = self.num_vertices_of(left) + self.num_vertices_of(right);
+        let subtree_flags = self.subtree_flags_of(left) | self.subtree_flags_of(right);
+
+        let node = &mut self.nodes[x];
+        node.size = size;
+        node.num_vertices = num_vertices + usize::from(node.vertex != NIL);
+        node.subtree_flags = subtree_flags | node.flags;
+    }
+
+    fn set_parent(&mut self, x: usize, parent: usize) {
+        if x != NIL {
+            self.nodes[x].parent = parent;
+        }
+    }
+
+    fn size_of(&self, x: usize) -> usize {
+        if x == NIL {
+            0
+        } else {
+            self.nodes[x].size
+        }
+    }
+
+    fn num_vertices_of(&self, x: usize) -> usize {
+        if x == NIL {
+            0
+        } else {
+            self.nodes[x].num_vertices
+        }
+    }
+
+    fn subtree_flags_of(&self, x: usize) -> u8 {
+        if x == NIL {
+            0
+        } else {
+            self.nodes[x].subtree_flags
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_euler_tour_forest() {
+        let mut forest = EulerTourForest::new();
+        let v: Vec<usize> = (0..6).map(|i| forest.add_vertex(i)).collect();
+        assert!(!forest.connected(v[0], v[1]));
+
+        // 0 - 1 - 2 - 3, 1 - 4, 5
+        let e01 = forest.link(v[0], v[1]);
+        let e12 = forest.link(v[2], v[1]);
+        let e23 = forest.link(v[2], v[3]);
+        let e14 = forest.link(v[4], v[1]);
+        assert!(forest.connected(v[0], v[3]));
+        assert!(forest.connected(v[4], v[3]));
+        assert!(!forest.connected(v[4], v[5]));
+        assert_eq!(forest.num_vertices(v[2]), 5);
+        assert_eq!(forest.num_vertices(v[5]), 1);
+
+        forest.set_flags(v[3], 1);
+        forest.set_flags(v[4], 2);
+        assert_eq!(forest.find_flagged(v[0], 1), Some(3));
+        assert_eq!(forest.find_flagged(v[0], 2), Some(4));
+        assert_eq!(forest.find_flagged(v[5], 3), None);
+
+        // 0 - 1 - 4, 2 - 3, 5
+        forest.cut(e12);
+        assert!(forest.connected(v[0], v[4]));
+        assert!(forest.connected(v[2], v[3]));
+        assert!(!forest.connected(v[1], v[2]));
+        assert_eq!(forest.num_vertices(v[4]), 3);
+        assert_eq!(forest.num_vertices(v[3]), 2);
+        assert_eq!(forest.find_flagged(v[0], 1), None);
+        assert_eq!(forest.find_flagged(v[2], 1), Some(3));
+        assert!([0, 1, 4].contains(&forest.first_vertex(v[1])));
+
+        // Arc nodes are reused.
+        forest.cut(e01);
+        forest.cut(e23);
+        forest.cut(e14);
+        assert!((0..6).all(|i| forest.num_vertices(v[i]) == 1));
+        forest.link(v[5], v[0]);
+        assert_eq!(forest.nodes.len(), 6 + 8);
+        assert!(forest.connected(v[0], v[5]));
+        forest.set_flags(v[3], 0);
+        assert_eq!(forest.find_flagged(v[3], 1), None);
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::hash_map;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Index;
+
+use crate::union_find::{Arg, Error, Result};
+
+/// The items of disjoint sets that never remove items, numbered by dense
+/// indices in insertion order. Data about the items can then be kept in
+/// vectors indexed by the same indices.
+#[derive(Clone, Debug, Default)]
+pub struct ItemTable<T> {
+    items: Vec<T>,
+    indices: HashMap<T, usize>,
+}
+
+impl<T> ItemTable<T>
+where
+    T: Eq + Hash + Clone,
+{
+    pub fn new() -> Self {
+        ItemTable {
+            items: Vec::new(),
+            indices: HashMap::new(),
+        }
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.indices.contains_key(item)
+    }
+
+    /// Add `item` and return its index. If `item` already exists, an error
+    /// is returned.
+    pub fn insert(&mut self, item: T) -> Result<usize> {
+        match self.indices.entry(item) {
+            hash_map::Entry::Occupied(_) => Err(Error::item_exists()),
+            hash_map::Entry::Vacant(entry) => {
+                let index = self.items.len();
+                self.items.push(entry.key().clone());
+                entry.insert(index);
+                Ok(index)
+            }
+        }
+    }
+
+    /// Get the index of `item`, reporting it as argument `arg` if it does not
+    /// exist.
+    pub fn index_of(&self, item: &T, arg: Arg) -> Result<usize> {
+        self.indices
+            .get(item)
+            .copied()
+            .ok_or(Error::item_not_found(arg))
+    }
+}
+
+impl<T> ItemTable<T> {
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Get the items in insertion order.
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+}
+
+impl<T> Index<usize> for ItemTable<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        &self.items[index]
+    }
+}
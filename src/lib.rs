@@ -1,3 +1,22 @@
+pub mod algorithms;
+pub mod compression;
+pub mod concurrent_disjoint_sets;
+pub mod dense_disjoint_sets;
 pub mod disjoint_sets;
+pub mod disjoint_sets_with;
+pub mod dynamic_connectivity;
+mod euler_tour_forest;
+mod item_table;
+pub mod link;
 mod node;
+pub mod observer;
+pub mod parity_disjoint_sets;
+mod persistent_array;
+pub mod persistent_disjoint_sets;
+pub mod rollback_disjoint_sets;
+mod set_stats;
+#[cfg(test)]
+mod test_rng;
+pub mod timestamped_disjoint_sets;
 pub mod union_find;
+pub mod weighted_disjoint_sets;
@@ -0,0 +1,81 @@
+/// A representative about to be linked, as seen by a [`LinkPolicy`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Root {
+    /// An index that is unique among the items of the disjoint sets.
+    pub index: u64,
+    /// The number of items in the set.
+    pub size: usize,
+    /// An upper bound on the height of the tree.
+    pub rank: usize,
+}
+
+/// Decides which of two representatives becomes the parent of the other when
+/// their sets are merged. Every policy yields the same partition, only the
+/// shape of the trees differs.
+pub trait LinkPolicy {
+    /// Return whether `x` should be linked under `y`, rather than `y` under
+    /// `x`.
+    fn link_under(&self, x: Root, y: Root) -> bool;
+}
+
+/// Link the smaller set under the larger one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BySize;
+
+impl LinkPolicy for BySize {
+    fn link_under(&self, x: Root, y: Root) -> bool {
+        x.size < y.size
+    }
+}
+
+/// Link the tree of smaller rank under the one of larger rank.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ByRank;
+
+impl LinkPolicy for ByRank {
+    fn link_under(&self, x: Root, y: Root) -> bool {
+        x.rank < y.rank
+    }
+}
+
+/// Link by a pseudo-random priority derived from the index of each
+/// representative and a seed, which keeps trees shallow in expectation
+/// without any bookkeeping.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ByRandomIndex {
+    pub seed: u64,
+}
+
+impl ByRandomIndex {
+    /// The SplitMix64 finalizer.
+    fn priority(&self, index: u64) -> u64 {
+        let mut z = index
+            .wrapping_add(self.seed)
+            .wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+impl LinkPolicy for ByRandomIndex {
+    fn link_under(&self, x: Root, y: Root) -> bool {
+        (self.priority(x.index), x.index) < (self.priority(y.index), y.index)
+    }
+}
+
+/// Link the roots `x` and `y` by `policy`, and return the root that goes
+/// under the other one and the root of the merged tree.
+pub(crate) fn link_roots(policy: &impl LinkPolicy, x: Root, y: Root) -> (Root, Root) {
+    let (child, parent) = if policy.link_under(x, y) {
+        (x, y)
+    } else {
+        (y, x)
+    };
+    let merged = Root {
+        index: parent.index,
+        size: parent.size + child.size,
+        rank: parent.rank.max(child.rank + 1),
+    };
+    (child, merged)
+}
@@ -0,0 +1,457 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::process::ExitCode;
+
+use union_find::disjoint_sets::DisjointSets;
+use union_find::union_find::UnionFind;
+
+const USAGE: &str = "\
+Usage: union-find [OPTIONS] [FILE]
+
+Compute the connected components of an edge list read from FILE, or from
+standard input if FILE is omitted or `-`.
+
+Every line holds two node names separated by whitespace, a comma or a tab.
+Further columns are ignored, a line with a single name adds an isolated node,
+and empty lines and lines starting with `#` are skipped. Names separated by
+commas or tabs may be quoted as in CSV, e.g. `\"a, \"\"b\"\"\",c`, but cannot
+span lines.
+
+Options:
+  --header                   Skip the first line that holds names
+  --format <json|csv|lines>  Output format of the components [default: lines]
+  --min-size <N>             Only report components with at least N nodes
+  --count-only               Print the number of components instead
+  --query <A> <B>            Print whether A and B are connected
+  -h, --help                 Print this help";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Json,
+    Csv,
+    Lines,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Options {
+    input: Option<String>,
+    header: bool,
+    format: Format,
+    min_size: usize,
+    count_only: bool,
+    query: Option<(String, String)>,
+}
+
+fn next_value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("missing value for `{}`", flag))
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        input: None,
+        header: false,
+        format: Format::Lines,
+        min_size: 1,
+        count_only: false,
+        query: None,
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--header" => options.header = true,
+            "--format" => {
+                options.format = match next_value(&mut args, &arg)?.as_str() {
+                    "json" => Format::Json,
+                    "csv" => Format::Csv,
+                    "lines" => Format::Lines,
+                    other => return Err(format!("unknown format `{}`", other)),
+                }
+            }
+            "--min-size" => {
+                let n = next_value(&mut args, &arg)?;
+                options.min_size = n
+                    .parse()
+                    .map_err(|_| format!("invalid minimum size `{}`", n))?;
+            }
+            "--count-only" => options.count_only = true,
+            "--query" => {
+                let a = next_value(&mut args, &arg)?;
+                let b = next_value(&mut args, &arg)?;
+                options.query = Some((a, b));
+            }
+            flag if flag.starts_with("--") => return Err(format!("unknown option `{}`", flag)),
+            _ if options.input.is_some() => return Err("more than one input file".to_string()),
+            _ => options.input = Some(arg),
+        }
+    }
+
+    Ok(Some(options))
+}
+
+/// Split a line into at most two node names, ignoring empty fields. Returns
+/// `None` for lines to skip.
+fn parse_line(line: &str) -> Result<Option<Vec<String>>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let fields = match delimiter(line) {
+        Some(delimiter) => split_fields(line, delimiter)?,
+        None => line.split_whitespace().map(str::to_string).collect(),
+    };
+    let names: Vec<String> = fields
+        .into_iter()
+        .filter(|f| !f.is_empty())
+        .take(2)
+        .collect();
+    if names.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(names))
+    }
+}
+
+/// Get the first comma or tab of a line outside of quotes, or inside them if
+/// there is none.
+fn delimiter(line: &str) -> Option<char> {
+    let mut quoted = false;
+    line.chars()
+        .find(|&c| {
+            if c == '"' {
+                quoted = !quoted;
+            }
+            !quoted && (c == ',' || c == '\t')
+        })
+        .or_else(|| line.chars().find(|&c| c == ',' || c == '\t'))
+}
+
+/// Split a line at `delimiter` into trimmed fields. A field may be quoted,
+/// with `""` standing for a quote, to contain the delimiter or whitespace.
+fn split_fields(line: &str, delimiter: char) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        let mut field = String::new();
+        while chars
+            .next_if(|c| *c != delimiter && c.is_whitespace())
+            .is_some()
+        {}
+        if chars.next_if_eq(&'"').is_some() {
+            loop {
+                match chars.next() {
+                    Some('"') if chars.next_if_eq(&'"').is_some() => field.push('"'),
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => return Err("unterminated quoted name".to_string()),
+                }
+            }
+            while let Some(c) = chars.next_if(|c| *c != delimiter) {
+                if !c.is_whitespace() {
+                    return Err("unexpected text after a quoted name".to_string());
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| *c != delimiter) {
+                field.push(c);
+            }
+            field.truncate(field.trim_end().len());
+        }
+        fields.push(field);
+        if chars.next().is_none() {
+            return Ok(fields);
+        }
+    }
+}
+
+/// Read an edge list, skipping its first line that holds names if `header`
+/// is set.
+fn read_edges(input: impl BufRead, header: bool) -> Result<DisjointSets<String>, Box<dyn Error>> {
+    let mut sets = DisjointSets::new();
+    let mut skip = header;
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        let Some(mut names) = parse_line(&line).map_err(|e| format!("line {}: {}", i + 1, e))?
+        else {
+            continue;
+        };
+        if skip {
+            skip = false;
+            continue;
+        }
+
+        let y = names.pop().unwrap();
+        match names.pop() {
+            Some(x) => sets.union_or_insert(x, y),
+            None => {
+                sets.find_or_insert(y);
+            }
+        }
+    }
+    Ok(sets)
+}
+
+/// Order node names numerically if both are integers, and lexicographically
+/// otherwise, with integers first.
+fn compare_names(x: &str, y: &str) -> Ordering {
+    match (x.parse::<i64>(), y.parse::<i64>()) {
+        (Ok(x), Ok(y)) => x.cmp(&y),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => x.cmp(y),
+    }
+}
+
+/// Get the components with at least `min_size` nodes, largest first, with
+/// their nodes sorted.
+fn components(sets: &DisjointSets<String>, min_size: usize) -> Vec<Vec<&str>> {
+    let mut components: Vec<Vec<&str>> = sets
+        .sets()
+        .filter(|set| set.len() >= min_size)
+        .map(|set| {
+            let mut set: Vec<&str> = set.into_iter().map(String::as_str).collect();
+            set.sort_by(|x, y| compare_names(x, y));
+            set
+        })
+        .collect();
+    components.sort_by(|x, y| {
+        y.len()
+            .cmp(&x.len())
+            .then_with(|| compare_names(x[0], y[0]))
+    });
+    components
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn write_components(
+    out: &mut impl Write,
+    components: &[Vec<&str>],
+    format: Format,
+) -> io::Result<()> {
+    match format {
+        Format::Lines => {
+            for component in components {
+                writeln!(out, "{}", component.join(" "))?;
+            }
+        }
+        Format::Csv => {
+            writeln!(out, "component,node")?;
+            for (i, component) in components.iter().enumerate() {
+                for node in component {
+                    writeln!(out, "{},{}", i, csv_field(node))?;
+                }
+            }
+        }
+        Format::Json => {
+            let components: Vec<String> = components
+                .iter()
+                .map(|c| {
+                    let nodes: Vec<String> = c.iter().map(|n| json_string(n)).collect();
+                    format!("[{}]", nodes.join(","))
+                })
+                .collect();
+            writeln!(out, "[{}]", components.join(","))?;
+        }
+    }
+    Ok(())
+}
+
+fn run(options: Options) -> Result<(), Box<dyn Error>> {
+    let mut sets = match options.input.as_deref() {
+        None | Some("-") => read_edges(io::stdin().lock(), options.header)?,
+        Some(path) => read_edges(
+            BufReader::new(File::open(path).map_err(|e| format!("{}: {}", path, e))?),
+            options.header,
+        )?,
+    };
+
+    let mut out = BufWriter::new(io::stdout().lock());
+    if let Some((a, b)) = options.query {
+        for node in [&a, &b] {
+            if !sets.contains(node) {
+                return Err(format!("node `{}` does not occur in the input", node).into());
+            }
+        }
+        let connected = sets.same_set(&a, &b)?;
+        writeln!(out, "{}", connected)?;
+    } else {
+        let components = components(&sets, options.min_size);
+        if options.count_only {
+            writeln!(out, "{}", components.len())?;
+        } else {
+            write_components(&mut out, &components, options.format)?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter()
+            .map(|a| a.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args(args(&[
+            "--format",
+            "csv",
+            "--min-size",
+            "2",
+            "--count-only",
+            "--header",
+            "edges.txt",
+        ]))
+        .unwrap()
+        .unwrap();
+        assert!(options.header);
+        assert_eq!(options.format, Format::Csv);
+        assert_eq!(options.min_size, 2);
+        assert!(options.count_only);
+        assert_eq!(options.input.as_deref(), Some("edges.txt"));
+
+        let options = parse_args(args(&["--query", "a", "b"])).unwrap().unwrap();
+        assert_eq!(options.query, Some(("a".to_string(), "b".to_string())));
+        assert_eq!(options.input, None);
+
+        assert_eq!(parse_args(args(&["--help"])).unwrap(), None);
+        assert!(parse_args(args(&["--format", "xml"])).is_err());
+        assert!(parse_args(args(&["--min-size"])).is_err());
+        assert!(parse_args(args(&["--query", "a"])).is_err());
+        assert!(parse_args(args(&["a", "b"])).is_err());
+    }
+
+    #[test]
+    fn test_components() {
+        let input = "\
+# comment
+1 2
+2\t3
+10,11,0.5
+ x  y
+
+12
+13,
+14,,15
+\t16\t
+, ,
+";
+        let sets = read_edges(input.as_bytes(), false).unwrap();
+        let components = components(&sets, 1);
+        assert_eq!(
+            components,
+            vec![
+                vec!["1", "2", "3"],
+                vec!["10", "11"],
+                vec!["14", "15"],
+                vec!["x", "y"],
+                vec!["12"],
+                vec!["13"],
+                vec!["16"]
+            ]
+        );
+
+        let mut out = Vec::new();
+        write_components(&mut out, &components[2..4], Format::Json).unwrap();
+        assert_eq!(out, b"[[\"14\",\"15\"],[\"x\",\"y\"]]\n");
+
+        let mut out = Vec::new();
+        write_components(&mut out, &components[1..2], Format::Csv).unwrap();
+        assert_eq!(out, b"component,node\n0,10\n0,11\n");
+
+        let mut out = Vec::new();
+        write_components(&mut out, &components[..1], Format::Lines).unwrap();
+        assert_eq!(out, b"1 2 3\n");
+
+        assert_eq!(super::components(&sets, 2).len(), 4);
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let names = |line| parse_line(line).unwrap().unwrap();
+        assert_eq!(names("\"a,b\",c"), ["a,b", "c"]);
+        assert_eq!(names("\"a\tb\"\tc\td"), ["a\tb", "c"]);
+        assert_eq!(names(" \"say \"\"hi\"\"\" , b "), ["say \"hi\"", "b"]);
+        assert_eq!(names("\"\",a,\"\""), ["a"]);
+        assert_eq!(names("\" a \",b"), [" a ", "b"]);
+        assert_eq!(names("a b,c"), ["a b", "c"]);
+        assert_eq!(names("a  b c"), ["a", "b"]);
+        assert_eq!(names("\"a,b\""), ["a,b"]);
+        assert_eq!(parse_line("\"\", ").unwrap(), None);
+        assert!(parse_line("\"a,b").is_err());
+        assert!(parse_line("\"a\"b,c").is_err());
+
+        // Names written by the CSV output are read back unchanged.
+        let name = "x, \"y\"";
+        assert_eq!(names(&format!("{},z", csv_field(name))), [name, "z"]);
+    }
+
+    #[test]
+    fn test_header() {
+        let input = "# nodes\nsource,target\na,b\nb,c\n";
+        let sets = read_edges(input.as_bytes(), true).unwrap();
+        assert_eq!(components(&sets, 1), vec![vec!["a", "b", "c"]]);
+        let sets = read_edges(input.as_bytes(), false).unwrap();
+        assert_eq!(components(&sets, 1).len(), 2);
+
+        let error = read_edges("a,b\n\"c,d\n".as_bytes(), false).unwrap_err();
+        assert_eq!(error.to_string(), "line 2: unterminated quoted name");
+    }
+}
@@ -2,14 +2,22 @@ use std::cell::Cell;
 use std::fmt::Debug;
 use std::hash::Hash;
 
-/// Node is a wrapper around a element in the disjoin sets with parent and rank.
+/// Node is a wrapper around a element in the disjoin sets with parent, rank
+/// and size.
 #[derive(Clone, Debug)]
 pub struct Node<T: Copy> {
     item: T,
     // Use `Cell` for internal mutability.
     /// A node is the representative of the set if its parent is itself.
     parent: Cell<T>,
+    /// An upper bound on the height of the subtree rooted at this node.
     rank: Cell<usize>,
+    /// The number of items in the set. Only meaningful for representatives.
+    size: Cell<usize>,
+    /// The neighbors in a circular list of all members of the set, if the
+    /// disjoint sets maintain one.
+    prev: Cell<T>,
+    next: Cell<T>,
 }
 
 impl<T> Node<T>
@@ -20,7 +28,10 @@ where
         Node {
             item,
             parent: item.into(),
-            rank: 1.into(),
+            rank: 0.into(),
+            size: 1.into(),
+            prev: item.into(),
+            next: item.into(),
         }
     }
 
@@ -44,6 +55,30 @@ where
         self.rank.set(rank);
     }
 
+    pub fn size(&self) -> usize {
+        self.size.get()
+    }
+
+    pub fn set_size(&self, size: usize) {
+        self.size.set(size);
+    }
+
+    pub fn prev(&self) -> T {
+        self.prev.get()
+    }
+
+    pub fn set_prev(&self, prev: T) {
+        self.prev.set(prev);
+    }
+
+    pub fn next(&self) -> T {
+        self.next.get()
+    }
+
+    pub fn set_next(&self, next: T) {
+        self.next.set(next);
+    }
+
     pub fn is_representative(&self) -> bool {
         self.item == self.parent.get()
     }
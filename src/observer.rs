@@ -0,0 +1,27 @@
+/// Receives the changes made to [`DisjointSets`], for example to keep an
+/// external index in sync with the sets.
+///
+/// Every method does nothing by default. Observers are a type parameter of
+/// [`DisjointSets`], so the default [`NoObserver`] is compiled away entirely.
+///
+/// [`DisjointSets`]: crate::disjoint_sets::DisjointSets
+pub trait UnionObserver<T> {
+    /// Called after a singleton set is created for `item`.
+    fn on_make_set(&mut self, _item: &T) {}
+
+    /// Called after two sets are merged. `winner` is the representative of
+    /// the merged set of `new_size` items, and `loser` is the former
+    /// representative of the other set.
+    fn on_union(&mut self, _winner: &T, _loser: &T, _new_size: usize) {}
+
+    /// Called after `item` is removed. If `item` was the representative of
+    /// a set with other items, `new_repr` is the new representative of that
+    /// set.
+    fn on_remove(&mut self, _item: &T, _new_repr: Option<&T>) {}
+}
+
+/// An observer that ignores all changes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NoObserver;
+
+impl<T> UnionObserver<T> for NoObserver {}
@@ -0,0 +1,149 @@
+use std::hash::Hash;
+
+use crate::union_find::Result;
+use crate::weighted_disjoint_sets::{Group, WeightedDisjointSets};
+
+/// The relation between two items in the same set.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Parity {
+    #[default]
+    Same,
+    Different,
+}
+
+/// Parities form a group under "exclusive or", with `Same` as identity.
+impl Group for Parity {
+    fn identity() -> Self {
+        Parity::Same
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        if self == other {
+            Parity::Same
+        } else {
+            Parity::Different
+        }
+    }
+
+    fn neg(&self) -> Self {
+        *self
+    }
+}
+
+/// Disjoint sets where any two items in the same set are known to be either
+/// on the same side or on different sides, e.g. the two colors of a
+/// bipartite graph.
+#[derive(Clone, Debug, Default)]
+pub struct ParityDisjointSets<T> {
+    sets: WeightedDisjointSets<T, Parity>,
+}
+
+impl<T> ParityDisjointSets<T>
+where
+    T: Eq + Hash + Clone,
+{
+    pub fn new() -> Self {
+        ParityDisjointSets {
+            sets: WeightedDisjointSets::new(),
+        }
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.sets.contains(item)
+    }
+
+    pub fn set_size(&mut self, item: &T) -> Result<usize> {
+        self.sets.set_size(item)
+    }
+
+    pub fn num_sets(&self) -> usize {
+        self.sets.num_sets()
+    }
+
+    pub fn num_items(&self) -> usize {
+        self.sets.num_items()
+    }
+
+    /// Create a new set containing only `item`. If `item` already exists in
+    /// the disjoint sets, an error is returned.
+    pub fn make_set(&mut self, item: T) -> Result<()> {
+        self.sets.make_set(item)
+    }
+
+    /// Find the representative of the set containing `item`. If `item` does
+    /// not exist in the disjoint sets, an error is returned.
+    pub fn find(&mut self, item: &T) -> Result<&T> {
+        self.sets.find(item)
+    }
+
+    /// Check if two items are in the same set. If `x` or `y` do not exist in
+    /// the disjoint sets, an error is returned.
+    pub fn same_set(&mut self, x: &T, y: &T) -> Result<bool> {
+        self.sets.same_set(x, y)
+    }
+
+    /// Get the relation between `x` and `y`, or `None` if they are in
+    /// different sets. If `x` or `y` do not exist in the disjoint sets, an
+    /// error is returned.
+    pub fn relation(&mut self, x: &T, y: &T) -> Result<Option<Parity>> {
+        self.sets.diff(x, y)
+    }
+
+    /// Merge the sets containing `x` and `y` such that `x` and `y` are on the
+    /// same side. If they are already known to be on different sides,
+    /// [`Error::Inconsistent`] is returned. If `x` or `y` do not exist in the
+    /// disjoint sets, an error is returned.
+    ///
+    /// [`Error::Inconsistent`]: crate::union_find::Error::Inconsistent
+    pub fn union_same(&mut self, x: &T, y: &T) -> Result<()> {
+        self.sets.union_with(x, y, Parity::Same)
+    }
+
+    /// Merge the sets containing `x` and `y` such that `x` and `y` are on
+    /// different sides. If they are already known to be on the same side,
+    /// [`Error::Inconsistent`] is returned. If `x` or `y` do not exist in the
+    /// disjoint sets, an error is returned.
+    ///
+    /// [`Error::Inconsistent`]: crate::union_find::Error::Inconsistent
+    pub fn union_different(&mut self, x: &T, y: &T) -> Result<()> {
+        self.sets.union_with(x, y, Parity::Different)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::union_find::Error;
+
+    #[test]
+    fn test_parity() {
+        let mut sets = ParityDisjointSets::new();
+        for i in 0..6 {
+            sets.make_set(i).unwrap();
+        }
+        assert_eq!(sets.relation(&0, &0).unwrap(), Some(Parity::Same));
+        assert_eq!(sets.relation(&0, &1).unwrap(), None);
+
+        // The path 0 - 1 - 2 - 3 is bipartite.
+        sets.union_different(&0, &1).unwrap();
+        sets.union_different(&1, &2).unwrap();
+        sets.union_different(&2, &3).unwrap();
+        assert_eq!(sets.relation(&0, &2).unwrap(), Some(Parity::Same));
+        assert_eq!(sets.relation(&0, &3).unwrap(), Some(Parity::Different));
+        assert_eq!(sets.relation(&3, &1).unwrap(), Some(Parity::Same));
+
+        // Closing an even cycle keeps it bipartite, an odd cycle does not.
+        sets.union_different(&3, &0).unwrap();
+        assert_eq!(sets.union_different(&0, &2), Err(Error::Inconsistent));
+        assert_eq!(sets.union_same(&0, &1), Err(Error::Inconsistent));
+        sets.union_same(&2, &0).unwrap();
+
+        // Merging two sets with a known relation.
+        sets.union_same(&4, &5).unwrap();
+        sets.union_different(&5, &1).unwrap();
+        assert_eq!(sets.relation(&4, &0).unwrap(), Some(Parity::Same));
+        assert_eq!(sets.relation(&4, &3).unwrap(), Some(Parity::Different));
+        assert_eq!(sets.num_sets(), 1);
+        assert_eq!(sets.set_size(&4).unwrap(), 6);
+    }
+}
@@ -0,0 +1,145 @@
+use std::rc::Rc;
+
+/// An immutable array where updating an element returns a new array that
+/// shares all but `O(log n)` nodes with the old one.
+#[derive(Debug)]
+pub struct PersistentArray<V> {
+    root: Option<Rc<Tree<V>>>,
+    len: usize,
+}
+
+/// A balanced binary tree whose leaves are the elements in order. A branch
+/// over `len` elements has `len / 2` of them on the left.
+#[derive(Debug)]
+enum Tree<V> {
+    Leaf(V),
+    Branch(Rc<Tree<V>>, Rc<Tree<V>>),
+}
+
+// Cloning only copies the root pointer, so `V` need not be `Clone`.
+impl<V> Clone for PersistentArray<V> {
+    fn clone(&self) -> Self {
+        PersistentArray {
+            root: self.root.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<V> PersistentArray<V> {
+    pub fn from_fn(len: usize, mut f: impl FnMut(usize) -> V) -> Self {
+        fn build<V>(start: usize, len: usize, f: &mut impl FnMut(usize) -> V) -> Rc<Tree<V>> {
+            if len == 1 {
+                Rc::new(Tree::Leaf(f(start)))
+            } else {
+                let mid = len / 2;
+                let left = build(start, mid, f);
+                let right = build(start + mid, len - mid, f);
+                Rc::new(Tree::Branch(left, right))
+            }
+        }
+
+        PersistentArray {
+            root: (len > 0).then(|| build(0, len, &mut f)),
+            len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn get(&self, mut index: usize) -> &V {
+        assert!(index < self.len, "index out of bounds");
+        let mut tree = self.root.as_ref().unwrap();
+        let mut len = self.len;
+        loop {
+            match &**tree {
+                Tree::Leaf(value) => return value,
+                Tree::Branch(left, right) => {
+                    let mid = len / 2;
+                    if index < mid {
+                        tree = left;
+                        len = mid;
+                    } else {
+                        tree = right;
+                        index -= mid;
+                        len -= mid;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Return a copy of the array with the element at `index` replaced by
+    /// `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn set(&self, index: usize, value: V) -> Self {
+        fn set<V>(tree: &Rc<Tree<V>>, len: usize, index: usize, value: V) -> Rc<Tree<V>> {
+            match &**tree {
+                Tree::Leaf(_) => Rc::new(Tree::Leaf(value)),
+                Tree::Branch(left, right) => {
+                    let mid = len / 2;
+                    if index < mid {
+                        Rc::new(Tree::Branch(set(left, mid, index, value), right.clone()))
+                    } else {
+                        Rc::new(Tree::Branch(
+                            left.clone(),
+                            set(right, len - mid, index - mid, value),
+                        ))
+                    }
+                }
+            }
+        }
+
+        assert!(index < self.len, "index out of bounds");
+        PersistentArray {
+            root: Some(set(self.root.as_ref().unwrap(), self.len, index, value)),
+            len: self.len,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_persistent_array() {
+        let empty: PersistentArray<usize> = PersistentArray::from_fn(0, |i| i);
+        assert_eq!(empty.len(), 0);
+
+        let v0 = PersistentArray::from_fn(10, |i| i * 10);
+        let v1 = v0.set(3, 31);
+        let v2 = v1.set(9, 91).set(0, 1);
+        let v3 = v0.set(3, 32);
+
+        assert_eq!(
+            (0..10).map(|i| *v0.get(i)).collect::<Vec<_>>(),
+            vec![0, 10, 20, 30, 40, 50, 60, 70, 80, 90]
+        );
+        assert_eq!(
+            (0..10).map(|i| *v1.get(i)).collect::<Vec<_>>(),
+            vec![0, 10, 20, 31, 40, 50, 60, 70, 80, 90]
+        );
+        assert_eq!(
+            (0..10).map(|i| *v2.get(i)).collect::<Vec<_>>(),
+            vec![1, 10, 20, 31, 40, 50, 60, 70, 80, 91]
+        );
+        assert_eq!(*v3.get(3), 32);
+
+        // The untouched half is shared.
+        let (Tree::Branch(_, r0), Tree::Branch(_, r1)) =
+            (&**v0.root.as_ref().unwrap(), &**v1.root.as_ref().unwrap())
+        else {
+            panic!("expected branches");
+        };
+        assert!(Rc::ptr_eq(r0, r1));
+    }
+}
@@ -0,0 +1,207 @@
+use std::hash::Hash;
+use std::rc::Rc;
+
+use crate::item_table::ItemTable;
+use crate::link::{link_roots, ByRank, Root};
+use crate::persistent_array::PersistentArray;
+use crate::union_find::{Arg, Result};
+
+/// The parent, rank and size of an item.
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    /// An item is the representative of its set if its parent is itself.
+    parent: usize,
+    rank: usize,
+    /// Only meaningful for representatives.
+    size: usize,
+}
+
+/// Persistent disjoint sets over a fixed collection of items that implement
+/// union-find with union by rank, following Conchon and Filliâtre.
+///
+/// Every version is immutable: `union` returns a new version and leaves the
+/// old one intact, so any number of versions can be kept and queried. The
+/// parents are kept in a persistent array, so a union only copies
+/// `O(log n)` array nodes and shares the rest with the old version. Path
+/// compression would modify old versions, so it is not performed, and `find`
+/// takes `O(log² n)` time. Cloning a version is cheap.
+#[derive(Debug)]
+pub struct PersistentDisjointSets<T> {
+    /// The items of all versions, which never change.
+    items: Rc<ItemTable<T>>,
+    entries: PersistentArray<Entry>,
+    num_sets: usize,
+}
+
+// Cloning only copies pointers, so `T` need not be `Clone`.
+impl<T> Clone for PersistentDisjointSets<T> {
+    fn clone(&self) -> Self {
+        PersistentDisjointSets {
+            items: self.items.clone(),
+            entries: self.entries.clone(),
+            num_sets: self.num_sets,
+        }
+    }
+}
+
+impl<T> PersistentDisjointSets<T>
+where
+    T: Eq + Hash + Clone,
+{
+    /// Create the first version, with a singleton set for every item. If an
+    /// item occurs twice, an error is returned.
+    pub fn new(items: impl IntoIterator<Item = T>) -> Result<Self> {
+        let mut table = ItemTable::new();
+        for item in items {
+            table.insert(item)?;
+        }
+
+        Ok(PersistentDisjointSets {
+            entries: PersistentArray::from_fn(table.len(), |i| Entry {
+                parent: i,
+                rank: 0,
+                size: 1,
+            }),
+            num_sets: table.len(),
+            items: Rc::new(table),
+        })
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.items.contains(item)
+    }
+
+    pub fn num_sets(&self) -> usize {
+        self.num_sets
+    }
+
+    pub fn num_items(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Find the representative of the set containing `item` in this version.
+    /// If `item` does not exist in the disjoint sets, an error is returned.
+    pub fn find(&self, item: &T) -> Result<&T> {
+        let repr = self.find_repr(self.items.index_of(item, Arg::Item)?);
+        Ok(&self.items[repr])
+    }
+
+    /// Check if two items are in the same set in this version. If `x` or `y`
+    /// do not exist in the disjoint sets, an error is returned.
+    pub fn same_set(&self, x: &T, y: &T) -> Result<bool> {
+        let x_repr = self.find_repr(self.items.index_of(x, Arg::X)?);
+        let y_repr = self.find_repr(self.items.index_of(y, Arg::Y)?);
+        Ok(x_repr == y_repr)
+    }
+
+    pub fn set_size(&self, item: &T) -> Result<usize> {
+        let repr = self.find_repr(self.items.index_of(item, Arg::Item)?);
+        Ok(self.entries.get(repr).size)
+    }
+
+    /// Return a new version in which the sets containing `x` and `y` are
+    /// merged. If `x` or `y` do not exist in the disjoint sets, an error is
+    /// returned.
+    pub fn union(&self, x: &T, y: &T) -> Result<Self> {
+        let x_repr = self.find_repr(self.items.index_of(x, Arg::X)?);
+        let y_repr = self.find_repr(self.items.index_of(y, Arg::Y)?);
+
+        if x_repr == y_repr {
+            return Ok(self.clone());
+        }
+
+        let root = |index: usize| {
+            let entry = self.entries.get(index);
+            Root {
+                index: index as u64,
+                size: entry.size,
+                rank: entry.rank,
+            }
+        };
+        let (child, parent) = link_roots(&ByRank, root(x_repr), root(y_repr));
+        let (child, parent_index) = (child.index as usize, parent.index as usize);
+        let entries = self
+            .entries
+            .set(
+                child,
+                Entry {
+                    parent: parent_index,
+                    ..*self.entries.get(child)
+                },
+            )
+            .set(
+                parent_index,
+                Entry {
+                    parent: parent_index,
+                    rank: parent.rank,
+                    size: parent.size,
+                },
+            );
+        Ok(PersistentDisjointSets {
+            items: self.items.clone(),
+            entries,
+            num_sets: self.num_sets - 1,
+        })
+    }
+}
+
+impl<T> PersistentDisjointSets<T> {
+    /// Assumes `index` exists.
+    fn find_repr(&self, mut index: usize) -> usize {
+        loop {
+            let parent = self.entries.get(index).parent;
+            if parent == index {
+                return index;
+            }
+            index = parent;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::union_find::Error;
+
+    #[test]
+    fn test_versions() {
+        assert!(matches!(
+            PersistentDisjointSets::new([1, 2, 1]),
+            Err(Error::ItemExists { .. })
+        ));
+
+        let v0 = PersistentDisjointSets::new(1..=6).unwrap();
+        assert!(matches!(
+            v0.union(&1, &7),
+            Err(Error::ItemNotFound { arg: Arg::Y, .. })
+        ));
+
+        // v1: (1, 2), v2: (1, 2, 3), v3: (1, 2), (4, 5)
+        let v1 = v0.union(&1, &2).unwrap();
+        let v2 = v1.union(&3, &2).unwrap();
+        let v3 = v1.union(&4, &5).unwrap();
+
+        assert!(!v0.same_set(&1, &2).unwrap());
+        assert_eq!(v0.num_sets(), 6);
+        assert_eq!(*v0.find(&2).unwrap(), 2);
+
+        assert!(v1.same_set(&1, &2).unwrap());
+        assert!(!v1.same_set(&1, &3).unwrap());
+        assert_eq!(v1.num_sets(), 5);
+
+        assert!(v2.same_set(&1, &3).unwrap());
+        assert!(!v2.same_set(&4, &5).unwrap());
+        assert_eq!(v2.set_size(&3).unwrap(), 3);
+        assert_eq!(v2.num_sets(), 4);
+
+        assert!(v3.same_set(&4, &5).unwrap());
+        assert!(!v3.same_set(&1, &3).unwrap());
+        assert_eq!(v3.set_size(&1).unwrap(), 2);
+        assert_eq!(v3.num_sets(), 4);
+        assert_eq!(v3.find(&2).unwrap(), v1.find(&2).unwrap());
+
+        // Merging already merged sets yields an equivalent version.
+        let v4 = v3.union(&5, &4).unwrap();
+        assert_eq!(v4.num_sets(), v3.num_sets());
+    }
+}
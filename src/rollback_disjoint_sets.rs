@@ -0,0 +1,268 @@
+use std::hash::Hash;
+
+use crate::compression::NoCompression;
+use crate::disjoint_sets::{DisjointSets, Id};
+use crate::link::ByRank;
+use crate::union_find::{Arg, Result, UnionFind};
+
+/// A change to the disjoint sets that can be undone.
+#[derive(Clone, Debug)]
+enum Change<T> {
+    /// A new singleton set was created for the item.
+    MakeSet(T),
+    /// The representative `child` was linked under `parent`, whose rank was
+    /// `parent_rank` before the union.
+    Union {
+        child: Id,
+        parent: Id,
+        parent_rank: usize,
+    },
+}
+
+/// A point in the history of a [`RollbackDisjointSets`] that can be returned
+/// to with [`RollbackDisjointSets::rollback_to`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    generation: usize,
+    len: usize,
+    /// The stamp of the last change before the snapshot, if any.
+    last: Option<usize>,
+}
+
+/// Disjoint sets data structure that implements union-find with union by
+/// rank and supports undoing changes.
+///
+/// This is a [`DisjointSets`] without path compression, so that every union
+/// changes exactly one parent pointer and can be undone in constant time.
+/// `find` therefore takes logarithmic time.
+#[derive(Clone, Debug, Default)]
+pub struct RollbackDisjointSets<T> {
+    sets: DisjointSets<T, ByRank, NoCompression>,
+    /// Every change with a stamp that is unique within a generation, so that
+    /// a snapshot can tell if the changes before it were rolled back and
+    /// redone differently.
+    history: Vec<(usize, Change<T>)>,
+    next_stamp: usize,
+    // Incremented by `commit` to invalidate older snapshots.
+    generation: usize,
+}
+
+impl<T> RollbackDisjointSets<T>
+where
+    T: Eq + Hash + Clone,
+{
+    pub fn new() -> Self {
+        RollbackDisjointSets {
+            sets: DisjointSets::with_link_policy(ByRank),
+            history: Vec::new(),
+            next_stamp: 0,
+            generation: 0,
+        }
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.sets.contains(item)
+    }
+
+    pub fn set_size(&self, item: &T) -> Result<usize> {
+        let id = self.sets.id_of(item, Arg::Item)?;
+        Ok(self.sets.root(self.find_repr_id(id)).size)
+    }
+
+    pub fn num_sets(&self) -> usize {
+        self.sets.num_sets()
+    }
+
+    pub fn num_items(&self) -> usize {
+        self.sets.num_items()
+    }
+
+    /// Record the current state so that it can be restored later.
+    pub fn checkpoint(&self) -> Snapshot {
+        Snapshot {
+            generation: self.generation,
+            len: self.history.len(),
+            last: self.history.last().map(|(stamp, _)| *stamp),
+        }
+    }
+
+    /// Undo all `make_set` and `union` calls made since `snapshot` was taken.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `snapshot` was taken before the last call to `commit`, or if
+    /// the disjoint sets were already rolled back to an earlier snapshot.
+    pub fn rollback_to(&mut self, snapshot: Snapshot) {
+        let last = snapshot
+            .len
+            .checked_sub(1)
+            .and_then(|i| self.history.get(i))
+            .map(|(stamp, _)| *stamp);
+        assert!(
+            snapshot.generation == self.generation
+                && snapshot.len <= self.history.len()
+                && snapshot.last == last,
+            "snapshot is no longer valid"
+        );
+
+        while self.history.len() > snapshot.len {
+            match self.history.pop().unwrap().1 {
+                // The item is a singleton without children by now, so its
+                // node is dropped and the trees are not rebuilt.
+                Change::MakeSet(item) => {
+                    self.sets.remove(&item).unwrap();
+                }
+                Change::Union {
+                    child,
+                    parent,
+                    parent_rank,
+                } => self.sets.unlink(child, parent, parent_rank),
+            }
+        }
+    }
+
+    /// Make all changes so far permanent and discard the undo log. Snapshots
+    /// taken before the commit can no longer be rolled back to.
+    pub fn commit(&mut self) {
+        self.history.clear();
+        self.next_stamp = 0;
+        self.generation += 1;
+    }
+}
+
+impl<T> UnionFind<T> for RollbackDisjointSets<T>
+where
+    T: Eq + Hash + Clone,
+{
+    fn same_set(&mut self, x: &T, y: &T) -> Result<bool> {
+        self.sets.same_set(x, y)
+    }
+
+    fn find(&mut self, item: &T) -> Result<&T> {
+        self.sets.find(item)
+    }
+
+    fn make_set(&mut self, item: T) -> Result<()> {
+        self.sets.make_set(item.clone())?;
+        self.record(Change::MakeSet(item));
+        Ok(())
+    }
+
+    fn union(&mut self, x: &T, y: &T) -> Result<()> {
+        let x_repr = self.find_repr_id(self.sets.id_of(x, Arg::X)?);
+        let y_repr = self.find_repr_id(self.sets.id_of(y, Arg::Y)?);
+        if x_repr == y_repr {
+            return Ok(());
+        }
+
+        let (x_rank, y_rank) = (self.sets.root(x_repr).rank, self.sets.root(y_repr).rank);
+        let parent = self.sets.link(x_repr, y_repr);
+        let (child, parent_rank) = if parent == x_repr {
+            (y_repr, x_rank)
+        } else {
+            (x_repr, y_rank)
+        };
+        self.record(Change::Union {
+            child,
+            parent,
+            parent_rank,
+        });
+
+        Ok(())
+    }
+}
+
+impl<T> RollbackDisjointSets<T>
+where
+    T: Eq + Hash,
+{
+    fn record(&mut self, change: Change<T>) {
+        self.history.push((self.next_stamp, change));
+        self.next_stamp += 1;
+    }
+
+    /// Find the representative of the set containing `id`, which does not
+    /// change the tree without path compression.
+    fn find_repr_id(&self, id: Id) -> Id {
+        self.sets.find_repr_id_with(id, &mut |_, _| {})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::union_find::Error;
+
+    #[test]
+    fn test_rollback() {
+        let mut sets = RollbackDisjointSets::new();
+        for i in 1..=4 {
+            sets.make_set(i).unwrap();
+        }
+        let initial = sets.checkpoint();
+
+        // (1, 2), (3), (4)
+        sets.union(&1, &2).unwrap();
+        let snapshot = sets.checkpoint();
+
+        // (1, 2, 3, 4), (5)
+        sets.union(&3, &4).unwrap();
+        sets.union(&2, &3).unwrap();
+        sets.make_set(5).unwrap();
+        assert!(sets.same_set(&1, &4).unwrap());
+        assert_eq!(sets.set_size(&4).unwrap(), 4);
+        assert_eq!(sets.num_sets(), 2);
+        assert_eq!(sets.num_items(), 5);
+
+        sets.rollback_to(snapshot);
+        assert!(sets.same_set(&1, &2).unwrap());
+        assert!(!sets.same_set(&1, &3).unwrap());
+        assert!(!sets.same_set(&3, &4).unwrap());
+        assert_eq!(sets.set_size(&1).unwrap(), 2);
+        assert_eq!(sets.set_size(&3).unwrap(), 1);
+        assert_eq!(sets.num_sets(), 3);
+        assert_eq!(sets.num_items(), 4);
+        assert!(!sets.contains(&5));
+        assert_eq!(sets.sets.members(&1).unwrap().len(), 2);
+        assert_eq!(sets.sets.members(&3).unwrap(), vec![&3]);
+        assert!(matches!(sets.find(&5), Err(Error::ItemNotFound { .. })));
+
+        sets.rollback_to(initial);
+        assert!(!sets.same_set(&1, &2).unwrap());
+        assert_eq!(sets.num_sets(), 4);
+        assert_eq!(*sets.find(&2).unwrap(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "snapshot is no longer valid")]
+    fn test_rollback_after_commit() {
+        let mut sets = RollbackDisjointSets::new();
+        sets.make_set(1).unwrap();
+        sets.make_set(2).unwrap();
+        let snapshot = sets.checkpoint();
+
+        sets.union(&1, &2).unwrap();
+        sets.commit();
+        assert!(sets.same_set(&1, &2).unwrap());
+        sets.rollback_to(snapshot);
+    }
+
+    #[test]
+    #[should_panic(expected = "snapshot is no longer valid")]
+    fn test_rollback_past_snapshot() {
+        let mut sets = RollbackDisjointSets::new();
+        for i in 1..=3 {
+            sets.make_set(i).unwrap();
+        }
+        let a = sets.checkpoint();
+        sets.union(&1, &2).unwrap();
+        let b = sets.checkpoint();
+
+        // Rolling back to `a` undoes the union that `b` depends on, even
+        // though the history grows as long again.
+        sets.rollback_to(a);
+        sets.union(&2, &3).unwrap();
+        sets.union(&1, &3).unwrap();
+        sets.rollback_to(b);
+    }
+}
@@ -0,0 +1,55 @@
+use std::collections::BTreeMap;
+
+/// Statistics about the sizes of all sets, maintained incrementally as sets
+/// are created, merged and shrunk.
+#[derive(Clone, Debug, Default)]
+pub struct SetStats {
+    /// The number of sets of each size. Sizes without sets are absent.
+    histogram: BTreeMap<usize, usize>,
+    num_sets: usize,
+    largest: usize,
+}
+
+impl SetStats {
+    pub fn new() -> Self {
+        SetStats {
+            histogram: BTreeMap::new(),
+            num_sets: 0,
+            largest: 0,
+        }
+    }
+
+    pub fn num_sets(&self) -> usize {
+        self.num_sets
+    }
+
+    pub fn largest(&self) -> usize {
+        self.largest
+    }
+
+    pub fn histogram(&self) -> &BTreeMap<usize, usize> {
+        &self.histogram
+    }
+
+    /// Record a new set of `size` items.
+    pub fn add(&mut self, size: usize) {
+        *self.histogram.entry(size).or_default() += 1;
+        self.num_sets += 1;
+        self.largest = self.largest.max(size);
+    }
+
+    /// Record that a set of `size` items no longer exists.
+    ///
+    /// Assumes such a set was recorded.
+    pub fn remove(&mut self, size: usize) {
+        let count = self.histogram.get_mut(&size).unwrap();
+        *count -= 1;
+        if *count == 0 {
+            self.histogram.remove(&size);
+            if size == self.largest {
+                self.largest = self.histogram.keys().next_back().copied().unwrap_or(0);
+            }
+        }
+        self.num_sets -= 1;
+    }
+}
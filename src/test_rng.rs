@@ -0,0 +1,22 @@
+//! A xorshift64 generator, for reproducible pseudo-random tests and
+//! benchmarks without dependencies.
+
+pub(crate) struct TestRng {
+    state: u64,
+}
+
+impl TestRng {
+    pub(crate) fn new() -> Self {
+        TestRng {
+            state: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    /// Get the next number in `0..n`.
+    pub(crate) fn below(&mut self, n: u64) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state % n
+    }
+}
@@ -0,0 +1,221 @@
+use std::hash::Hash;
+
+use crate::item_table::ItemTable;
+use crate::link::{link_roots, ByRank, Root};
+use crate::node::Node;
+use crate::union_find::{Arg, Result, UnionFind};
+
+type Id = usize;
+
+/// Link time of representatives, which are not linked yet.
+const NEVER: u64 = u64::MAX;
+
+/// Disjoint sets data structure that implements union-find with union by rank
+/// and remembers when sets were merged.
+///
+/// Every call to `union` advances the time by one, so the `t`-th union
+/// happens at time `t`. Path compression is not performed, so the trees keep
+/// the history of all links and `find` takes logarithmic time. Link times
+/// increase on the way up from an item to its representative.
+#[derive(Clone, Debug, Default)]
+pub struct TimestampedDisjointSets<T> {
+    nodes: Vec<Node<Id>>,
+    /// The time at which each node was linked under its parent.
+    link_times: Vec<u64>,
+    items: ItemTable<T>,
+    time: u64,
+    num_sets: usize,
+}
+
+impl<T> TimestampedDisjointSets<T>
+where
+    T: Eq + Hash + Clone,
+{
+    pub fn new() -> Self {
+        TimestampedDisjointSets {
+            nodes: Vec::new(),
+            link_times: Vec::new(),
+            items: ItemTable::new(),
+            time: 0,
+            num_sets: 0,
+        }
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.items.contains(item)
+    }
+
+    pub fn set_size(&self, item: &T) -> Result<usize> {
+        let id = self.items.index_of(item, Arg::Item)?;
+        Ok(self.nodes[self.find_repr_id(id, NEVER)].size())
+    }
+
+    pub fn num_sets(&self) -> usize {
+        self.num_sets
+    }
+
+    pub fn num_items(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Get the number of unions performed so far.
+    pub fn time(&self) -> u64 {
+        self.time
+    }
+
+    /// Check if `x` and `y` were in the same set after the first `t` unions.
+    /// If `x` or `y` do not exist in the disjoint sets, an error is returned.
+    pub fn connected_at(&self, x: &T, y: &T, t: u64) -> Result<bool> {
+        let x_id = self.items.index_of(x, Arg::X)?;
+        let y_id = self.items.index_of(y, Arg::Y)?;
+        Ok(self.find_repr_id(x_id, t) == self.find_repr_id(y_id, t))
+    }
+
+    /// Get the time of the union that first put `x` and `y` in the same set,
+    /// `Some(0)` if `x` and `y` are equal, or `None` if they are still in
+    /// different sets. If `x` or `y` do not exist in the disjoint sets, an
+    /// error is returned.
+    pub fn first_connected(&self, x: &T, y: &T) -> Result<Option<u64>> {
+        let mut x_id = self.items.index_of(x, Arg::X)?;
+        let mut y_id = self.items.index_of(y, Arg::Y)?;
+        if self.find_repr_id(x_id, NEVER) != self.find_repr_id(y_id, NEVER) {
+            return Ok(None);
+        }
+
+        // Climb from whichever node was linked earlier, until both paths
+        // meet. The latest link on the way is the union that connected them.
+        let mut time = 0;
+        while x_id != y_id {
+            if self.link_times[x_id] < self.link_times[y_id] {
+                time = time.max(self.link_times[x_id]);
+                x_id = self.nodes[x_id].parent();
+            } else {
+                time = time.max(self.link_times[y_id]);
+                y_id = self.nodes[y_id].parent();
+            }
+        }
+        Ok(Some(time))
+    }
+}
+
+impl<T> UnionFind<T> for TimestampedDisjointSets<T>
+where
+    T: Eq + Hash + Clone,
+{
+    fn same_set(&mut self, x: &T, y: &T) -> Result<bool> {
+        self.connected_at(x, y, NEVER)
+    }
+
+    fn find(&mut self, item: &T) -> Result<&T> {
+        let id = self.items.index_of(item, Arg::Item)?;
+        Ok(&self.items[self.find_repr_id(id, NEVER)])
+    }
+
+    fn make_set(&mut self, item: T) -> Result<()> {
+        let id = self.items.insert(item)?;
+        self.nodes.push(Node::new(id));
+        self.link_times.push(NEVER);
+        self.num_sets += 1;
+        Ok(())
+    }
+
+    /// Merge the sets containing `x` and `y` and advance the time, even if
+    /// they are already the same set. If `x` or `y` do not exist in the
+    /// disjoint sets, an error is returned and the time does not change.
+    fn union(&mut self, x: &T, y: &T) -> Result<()> {
+        let x_id = self.items.index_of(x, Arg::X)?;
+        let y_id = self.items.index_of(y, Arg::Y)?;
+        let x_repr = self.find_repr_id(x_id, NEVER);
+        let y_repr = self.find_repr_id(y_id, NEVER);
+        self.time += 1;
+
+        if x_repr == y_repr {
+            return Ok(());
+        }
+
+        let (child, parent) = link_roots(&ByRank, self.root(x_repr), self.root(y_repr));
+        let (child, parent_id) = (child.index as Id, parent.index as Id);
+        self.nodes[child].set_parent(parent_id);
+        self.nodes[parent_id].set_rank(parent.rank);
+        self.nodes[parent_id].set_size(parent.size);
+        self.link_times[child] = self.time;
+        self.num_sets -= 1;
+
+        Ok(())
+    }
+}
+
+impl<T> TimestampedDisjointSets<T> {
+    fn root(&self, repr: Id) -> Root {
+        let node = &self.nodes[repr];
+        Root {
+            index: repr as u64,
+            size: node.size(),
+            rank: node.rank(),
+        }
+    }
+
+    /// Find the representative of the set containing `id` after the first
+    /// `t` unions.
+    ///
+    /// Assumes `id` exists.
+    fn find_repr_id(&self, mut id: Id, t: u64) -> Id {
+        loop {
+            let parent = self.nodes[id].parent();
+            if parent == id || self.link_times[id] > t {
+                return id;
+            }
+            id = parent;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::union_find::Error;
+
+    #[test]
+    fn test_time_travel() {
+        let mut sets = TimestampedDisjointSets::new();
+        for i in 1..=6 {
+            sets.make_set(i).unwrap();
+        }
+        assert!(matches!(
+            sets.union(&1, &7),
+            Err(Error::ItemNotFound { arg: Arg::Y, .. })
+        ));
+        assert_eq!(sets.time(), 0);
+
+        // 1: (1, 2), 2: (3, 4), 3: no-op, 4: (1, 2, 3, 4), 5: (1, 2, 3, 4, 5)
+        sets.union(&1, &2).unwrap();
+        sets.union(&3, &4).unwrap();
+        sets.union(&2, &1).unwrap();
+        sets.union(&4, &2).unwrap();
+        sets.union(&5, &1).unwrap();
+        assert_eq!(sets.time(), 5);
+        assert_eq!(sets.num_sets(), 2);
+        assert_eq!(sets.set_size(&3).unwrap(), 5);
+
+        assert_eq!(sets.first_connected(&1, &1).unwrap(), Some(0));
+        assert_eq!(sets.first_connected(&1, &2).unwrap(), Some(1));
+        assert_eq!(sets.first_connected(&4, &3).unwrap(), Some(2));
+        assert_eq!(sets.first_connected(&1, &4).unwrap(), Some(4));
+        assert_eq!(sets.first_connected(&3, &2).unwrap(), Some(4));
+        assert_eq!(sets.first_connected(&5, &3).unwrap(), Some(5));
+        assert_eq!(sets.first_connected(&5, &6).unwrap(), None);
+
+        assert!(!sets.connected_at(&1, &2, 0).unwrap());
+        assert!(sets.connected_at(&1, &2, 1).unwrap());
+        assert!(!sets.connected_at(&1, &3, 3).unwrap());
+        assert!(sets.connected_at(&1, &3, 4).unwrap());
+        assert!(!sets.connected_at(&5, &4, 4).unwrap());
+        assert!(sets.connected_at(&5, &4, 5).unwrap());
+        assert!(sets.connected_at(&5, &4, 100).unwrap());
+        assert!(!sets.connected_at(&6, &4, 100).unwrap());
+
+        assert!(sets.same_set(&5, &3).unwrap());
+        assert_eq!(*sets.find(&5).unwrap(), 3);
+        assert_eq!(*sets.find(&3).unwrap(), 3);
+    }
+}
@@ -1,8 +1,14 @@
+use std::fmt::{self, Debug, Display};
+
 pub trait UnionFind<T> {
     /// Check if two items are in the same set. If `x` or `y` do not exist in
     /// the disjoint sets, an error is returned.
     fn same_set(&mut self, x: &T, y: &T) -> Result<bool>;
 
+    /// Find the representative of the set containing `item`. If `item` does
+    /// not exist in the disjoint sets, an error is returned.
+    fn find(&mut self, item: &T) -> Result<&T>;
+
     /// Create a new set containing only `item`. If `item` already exists in
     /// the disjoint sets, an error is returned.
     fn make_set(&mut self, item: T) -> Result<()>;
@@ -14,10 +20,158 @@ pub trait UnionFind<T> {
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-#[derive(Debug)]
+/// The argument of an operation that an error refers to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arg {
+    /// The first of two items, e.g. `x` in `union(x, y)`.
+    X,
+    /// The second of two items, e.g. `y` in `union(x, y)`.
+    Y,
+    /// The only item of an operation, e.g. `item` in `find(item)`.
+    Item,
+}
+
+/// An error of a disjoint sets operation.
+///
+/// The `item` of a variant is only filled in by the disjoint sets over
+/// integers, [`DenseDisjointSets`] and [`ConcurrentDisjointSets`]. Other
+/// disjoint sets leave it empty, unless the error comes from one of the
+/// `*_with_items` methods of [`DisjointSets`] or [`Error::with_item`] is
+/// called on it.
+///
+/// [`DenseDisjointSets`]: crate::dense_disjoint_sets::DenseDisjointSets
+/// [`ConcurrentDisjointSets`]: crate::concurrent_disjoint_sets::ConcurrentDisjointSets
+/// [`DisjointSets`]: crate::disjoint_sets::DisjointSets
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Error {
     /// The item is not in the disjoint sets.
-    ItemNotFound,
+    ItemNotFound {
+        arg: Arg,
+        /// The `Debug` representation of the item, if known.
+        item: Option<String>,
+    },
     /// The item is already in the disjoint sets.
-    ItemExists,
+    ItemExists {
+        /// The `Debug` representation of the item, if known.
+        item: Option<String>,
+    },
+    /// The item cannot be added, e.g. because it is out of range or no more
+    /// items are accepted.
+    ItemRejected {
+        /// The `Debug` representation of the item, if known.
+        item: Option<String>,
+    },
+    /// The union contradicts what is already known about the items.
+    Inconsistent,
+    /// A serialized representation does not describe valid disjoint sets.
+    Malformed,
+}
+
+impl Error {
+    pub fn item_not_found(arg: Arg) -> Self {
+        Error::ItemNotFound { arg, item: None }
+    }
+
+    pub fn item_exists() -> Self {
+        Error::ItemExists { item: None }
+    }
+
+    pub fn item_rejected() -> Self {
+        Error::ItemRejected { item: None }
+    }
+
+    /// Attach the offending item to the error, so that it is included in the
+    /// error message.
+    ///
+    /// Disjoint sets over arbitrary items cannot do this themselves since
+    /// their items need not implement `Debug`, e.g.
+    /// `sets.union(&x, &y).map_err(|e| e.with_item(&y))`. [`DisjointSets`]
+    /// has `*_with_items` methods that do this for items that implement it.
+    ///
+    /// [`DisjointSets`]: crate::disjoint_sets::DisjointSets
+    pub fn with_item<T: Debug>(self, item: &T) -> Self {
+        let item = Some(format!("{:?}", item));
+        match self {
+            Error::ItemNotFound { arg, .. } => Error::ItemNotFound { arg, item },
+            Error::ItemExists { .. } => Error::ItemExists { item },
+            Error::ItemRejected { .. } => Error::ItemRejected { item },
+            Error::Inconsistent => Error::Inconsistent,
+            Error::Malformed => Error::Malformed,
+        }
+    }
+}
+
+impl Display for Arg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Arg::X => write!(f, "x"),
+            Arg::Y => write!(f, "y"),
+            Arg::Item => write!(f, "item"),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::ItemNotFound { arg, item: None } => {
+                write!(f, "argument `{}` is not in the disjoint sets", arg)
+            }
+            Error::ItemNotFound {
+                arg,
+                item: Some(item),
+            } => write!(
+                f,
+                "argument `{}` ({}) is not in the disjoint sets",
+                arg, item
+            ),
+            Error::ItemExists { item: None } => write!(f, "item is already in the disjoint sets"),
+            Error::ItemExists { item: Some(item) } => {
+                write!(f, "item {} is already in the disjoint sets", item)
+            }
+            Error::ItemRejected { item: None } => {
+                write!(f, "item cannot be added to the disjoint sets")
+            }
+            Error::ItemRejected { item: Some(item) } => {
+                write!(f, "item {} cannot be added to the disjoint sets", item)
+            }
+            Error::Inconsistent => write!(f, "union contradicts the known relation of the items"),
+            Error::Malformed => write!(f, "malformed disjoint sets representation"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_display() {
+        assert_eq!(
+            Error::item_not_found(Arg::Y).to_string(),
+            "argument `y` is not in the disjoint sets"
+        );
+        assert_eq!(
+            Error::item_not_found(Arg::X).with_item(&"a").to_string(),
+            "argument `x` (\"a\") is not in the disjoint sets"
+        );
+        assert_eq!(
+            Error::item_exists().to_string(),
+            "item is already in the disjoint sets"
+        );
+        assert_eq!(
+            Error::item_exists().with_item(&3).to_string(),
+            "item 3 is already in the disjoint sets"
+        );
+
+        assert_eq!(
+            Error::item_rejected().with_item(&7).to_string(),
+            "item 7 cannot be added to the disjoint sets"
+        );
+
+        let boxed: Box<dyn std::error::Error> = Box::new(Error::item_exists());
+        assert_eq!(boxed.to_string(), "item is already in the disjoint sets");
+    }
 }
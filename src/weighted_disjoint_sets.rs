@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::disjoint_sets::{DisjointSets, Id};
+use crate::union_find::{Arg, Error, Result, UnionFind};
+
+/// An abelian group, written additively.
+pub trait Group: Clone + PartialEq {
+    fn identity() -> Self;
+
+    fn add(&self, other: &Self) -> Self;
+
+    fn neg(&self) -> Self;
+
+    fn sub(&self, other: &Self) -> Self {
+        self.add(&other.neg())
+    }
+}
+
+macro_rules! impl_group_for_int {
+    ($($t:ty),*) => {
+        $(
+            impl Group for $t {
+                fn identity() -> Self {
+                    0
+                }
+
+                fn add(&self, other: &Self) -> Self {
+                    self.wrapping_add(*other)
+                }
+
+                fn neg(&self) -> Self {
+                    self.wrapping_neg()
+                }
+            }
+        )*
+    };
+}
+
+impl_group_for_int!(i8, i16, i32, i64, i128, isize);
+
+/// Disjoint sets where every item has a potential in the group `G`, and the
+/// difference of potentials is known for any two items in the same set.
+///
+/// Each node stores its potential relative to its parent. Path compression
+/// folds these into potentials relative to the new parent.
+#[derive(Clone, Debug, Default)]
+pub struct WeightedDisjointSets<T, G> {
+    sets: DisjointSets<T>,
+    /// `pot(id) - pot(parent(id))` for every ID.
+    potentials: HashMap<Id, G>,
+}
+
+impl<T, G> WeightedDisjointSets<T, G>
+where
+    T: Eq + Hash + Clone,
+    G: Group,
+{
+    pub fn new() -> Self {
+        WeightedDisjointSets {
+            sets: DisjointSets::new(),
+            potentials: HashMap::new(),
+        }
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.sets.contains(item)
+    }
+
+    pub fn set_size(&mut self, item: &T) -> Result<usize> {
+        self.sets.set_size(item)
+    }
+
+    pub fn num_sets(&self) -> usize {
+        self.sets.num_sets()
+    }
+
+    pub fn num_items(&self) -> usize {
+        self.sets.num_items()
+    }
+
+    /// Create a new set containing only `item`. If `item` already exists in
+    /// the disjoint sets, an error is returned.
+    pub fn make_set(&mut self, item: T) -> Result<()> {
+        self.sets.make_set(item.clone())?;
+        let id = self.sets.id_of(&item, Arg::Item).unwrap();
+        self.potentials.insert(id, G::identity());
+        Ok(())
+    }
+
+    /// Find the representative of the set containing `item`. If `item` does
+    /// not exist in the disjoint sets, an error is returned.
+    pub fn find(&mut self, item: &T) -> Result<&T> {
+        let id = self.sets.id_of(item, Arg::Item)?;
+        self.find_repr_id(id);
+        self.sets.find(item)
+    }
+
+    /// Check if two items are in the same set. If `x` or `y` do not exist in
+    /// the disjoint sets, an error is returned.
+    pub fn same_set(&mut self, x: &T, y: &T) -> Result<bool> {
+        Ok(self.diff(x, y)?.is_some())
+    }
+
+    /// Get `pot(x) - pot(y)`, or `None` if `x` and `y` are in different sets.
+    /// If `x` or `y` do not exist in the disjoint sets, an error is returned.
+    pub fn diff(&mut self, x: &T, y: &T) -> Result<Option<G>> {
+        let x_id = self.sets.id_of(x, Arg::X)?;
+        let y_id = self.sets.id_of(y, Arg::Y)?;
+        let (x_repr, x_pot) = self.find_repr_id(x_id);
+        let (y_repr, y_pot) = self.find_repr_id(y_id);
+
+        if x_repr == y_repr {
+            Ok(Some(x_pot.sub(&y_pot)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Merge the sets containing `x` and `y` such that `pot(x) - pot(y) = w`.
+    /// If `x` and `y` are already in the same set with a different
+    /// difference, [`Error::Inconsistent`] is returned and nothing changes.
+    /// If `x` or `y` do not exist in the disjoint sets, an error is returned.
+    pub fn union_with(&mut self, x: &T, y: &T, w: G) -> Result<()> {
+        let x_id = self.sets.id_of(x, Arg::X)?;
+        let y_id = self.sets.id_of(y, Arg::Y)?;
+        let (x_repr, x_pot) = self.find_repr_id(x_id);
+        let (y_repr, y_pot) = self.find_repr_id(y_id);
+
+        if x_repr == y_repr {
+            return if x_pot.sub(&y_pot) == w {
+                Ok(())
+            } else {
+                Err(Error::Inconsistent)
+            };
+        }
+
+        // With `x_pot` and `y_pot` relative to their representatives, the
+        // representative that becomes a child is placed at the potential
+        // that makes `pot(x) - pot(y) = w` hold.
+        let repr = self.sets.link(x_repr, y_repr);
+        if repr == x_repr {
+            let pot = x_pot.sub(&y_pot).sub(&w);
+            self.potentials.insert(y_repr, pot);
+        } else {
+            let pot = w.sub(&x_pot).add(&y_pot);
+            self.potentials.insert(x_repr, pot);
+        }
+
+        Ok(())
+    }
+
+    /// Find the representative of the set containing `id` and the potential
+    /// of `id` relative to it, performing path compression along the way.
+    ///
+    /// Assumes `id` exists.
+    fn find_repr_id(&mut self, id: Id) -> (Id, G) {
+        let potentials = &mut self.potentials;
+        let repr = self.sets.find_repr_id_with(id, &mut |id, old_parent| {
+            // `pot(id) - pot(old_parent)` plus
+            // `pot(old_parent) - pot(parent(old_parent))`.
+            let pot = potentials[&id].add(&potentials[&old_parent]);
+            potentials.insert(id, pot);
+        });
+
+        if repr == id {
+            (repr, G::identity())
+        } else {
+            (repr, potentials[&id].clone())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weighted_union_find() {
+        let mut sets: WeightedDisjointSets<&str, i64> = WeightedDisjointSets::new();
+        for item in ["a", "b", "c", "d", "e"] {
+            sets.make_set(item).unwrap();
+        }
+        assert!(matches!(
+            sets.diff(&"a", &"f"),
+            Err(Error::ItemNotFound { arg: Arg::Y, .. })
+        ));
+        assert_eq!(sets.diff(&"a", &"a").unwrap(), Some(0));
+        assert_eq!(sets.diff(&"a", &"b").unwrap(), None);
+
+        // a - b = 3, c - b = 5, d - e = -2
+        sets.union_with(&"a", &"b", 3).unwrap();
+        sets.union_with(&"c", &"b", 5).unwrap();
+        sets.union_with(&"d", &"e", -2).unwrap();
+        assert_eq!(sets.diff(&"a", &"c").unwrap(), Some(-2));
+        assert_eq!(sets.diff(&"c", &"a").unwrap(), Some(2));
+        assert_eq!(sets.diff(&"b", &"a").unwrap(), Some(-3));
+        assert_eq!(sets.diff(&"a", &"d").unwrap(), None);
+        assert_eq!(sets.num_sets(), 2);
+
+        // e - a = 10 merges the two sets.
+        sets.union_with(&"e", &"a", 10).unwrap();
+        assert_eq!(sets.diff(&"d", &"b").unwrap(), Some(11));
+        assert_eq!(sets.diff(&"c", &"e").unwrap(), Some(-8));
+        assert_eq!(sets.set_size(&"d").unwrap(), 5);
+        assert!(sets.same_set(&"a", &"e").unwrap());
+
+        // Consistent unions are no-ops, contradicting ones are rejected.
+        sets.union_with(&"d", &"c", 6).unwrap();
+        assert_eq!(sets.union_with(&"d", &"c", 7), Err(Error::Inconsistent));
+        assert_eq!(sets.diff(&"d", &"c").unwrap(), Some(6));
+    }
+}